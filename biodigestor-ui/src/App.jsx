@@ -1,10 +1,11 @@
-import { useEffect, useState } from 'react';
+import { useEffect, useMemo, useState } from 'react';
 import init, { BiodigestorModel } from '../public/pkg/bn_demo.js';
 import Header from './components/Header';
 import GraphPanel from './components/GraphPanel';
 import ControlPanel from './components/ControlPanel';
 import GraphModal from './components/GraphModal';
 import Footer from './components/Footer';
+import { buildSensores } from './config';
 import './App.css';
 
 function App() {
@@ -26,6 +27,7 @@ function App() {
   const [showGraphModal, setShowGraphModal] = useState(false);
 
   const isSmallScreen = windowWidth < 768;
+  const sensores = useMemo(() => (model ? buildSensores(model) : []), [model]);
 
   // Resize listener
   useEffect(() => {
@@ -57,26 +59,17 @@ function App() {
       try {
         const evidencePayload = JSON.parse(JSON.stringify(evidence));
         
-        // Inferencia para EstadoMicrobiano
-        const probMicrobiano = model.infer(evidencePayload, 'EstadoMicrobiano');
-        const microbianoObj = {};
-        probMicrobiano.forEach((value, key) => {
-          microbianoObj[key] = value;
-        });
-
-        // Inferencia para EstadoOperativo
-        const probOperativo = model.infer(evidencePayload, 'EstadoOperativo');
-        const operativoObj = {};
-        probOperativo.forEach((value, key) => {
-          operativoObj[key] = value;
-        });
+        // Una sola llamada devuelve las marginales de todos los nodos
+        const marginals = model.infer_all(evidencePayload);
 
         setResults({
-          EstadoMicrobiano: microbianoObj,
-          EstadoOperativo: operativoObj
+          EstadoMicrobiano: marginals.EstadoMicrobiano,
+          EstadoOperativo: marginals.EstadoOperativo
         });
 
       } catch (e) {
+        // Errores tipados del motor: { kind, message, details }
+        console.error(`Error en la inferencia (${e?.kind ?? 'desconocido'}):`, e?.message ?? e);
         setResults({
           EstadoMicrobiano: {},
           EstadoOperativo: {}
@@ -115,6 +108,7 @@ function App() {
         <ControlPanel
           isSmallScreen={isSmallScreen}
           onShowGraphModal={() => setShowGraphModal(true)}
+          sensores={sensores}
           evidence={evidence}
           onEvidenceChange={handleEvidenceChange}
           results={results}
@@ -1,7 +1,7 @@
 import { FaChartPie, FaChartLine, FaSlidersH, FaExclamationTriangle } from 'react-icons/fa';
-import { SENSORES, OBJETIVOS } from '../config';
+import { OBJETIVOS } from '../config';
 
-const ControlPanel = ({ isSmallScreen, onShowGraphModal, evidence, onEvidenceChange, results }) => {
+const ControlPanel = ({ isSmallScreen, onShowGraphModal, sensores, evidence, onEvidenceChange, results }) => {
   const basisClass = isSmallScreen ? 'basis-full' : 'basis-[30%]';
   return (
     <div className={`${basisClass} bg-white m-2 md:m-4 rounded-xl shadow-lg border border-slate-200 overflow-y-auto flex flex-col`}>
@@ -113,7 +113,7 @@ const ControlPanel = ({ isSmallScreen, onShowGraphModal, evidence, onEvidenceCha
           </h3>
 
           <div className="space-y-3">
-            {SENSORES.map(sensor => {
+            {sensores.map(sensor => {
               const IconComponent = sensor.icon;
               return (
                 <div key={sensor.id} className="bg-white rounded-lg p-3 border border-slate-200 shadow-sm">
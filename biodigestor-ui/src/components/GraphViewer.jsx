@@ -1,15 +1,19 @@
 import React, { useEffect, useRef, useState } from 'react';
 import { Network } from 'vis-network';
 
-const getOptions = (isSmallScreen) => ({
-  layout: {
+// Si el modelo trae posiciones (importado de Hugin/GeNIe) se respeta ese diseño.
+const hasPositions = (graphData) =>
+  !!graphData && graphData.nodes.length > 0 && graphData.nodes.every(n => n.x !== undefined && n.y !== undefined);
+
+const getOptions = (isSmallScreen, fixedLayout = false) => ({
+  layout: fixedLayout ? { hierarchical: { enabled: false } } : {
     hierarchical: {
       direction: isSmallScreen ? 'UD' : 'LR',
       sortMethod: 'directed',
       levelSeparation: isSmallScreen ? 250 : 350,
     },
   },
-  physics: isSmallScreen ? {
+  physics: fixedLayout ? { enabled: false } : isSmallScreen ? {
     enabled: true,
     barnesHut: {
       gravitationalConstant: -1000,
@@ -239,7 +243,7 @@ const GraphViewer = ({ model }) => {
         edges: graphData.edges.map(e => ({ from: e.from, to: e.to })),
       };
 
-      const network = new Network(containerRef.current, networkData, getOptions(isSmallScreen));
+      const network = new Network(containerRef.current, networkData, getOptions(isSmallScreen, hasPositions(graphData)));
       networkRef.current = network;
       
       network.on("click", (params) => {
@@ -282,10 +286,10 @@ const GraphViewer = ({ model }) => {
 
   useEffect(() => {
     if (networkRef.current) {
-      networkRef.current.setOptions(getOptions(isSmallScreen));
+      networkRef.current.setOptions(getOptions(isSmallScreen, hasPositions(graphData)));
       networkRef.current.stabilize();
     }
-  }, [isSmallScreen]);
+  }, [isSmallScreen, graphData]);
 
   // Función mejorada para formatear claves
   const formatKey = (keyString) => {
@@ -432,7 +436,8 @@ const GraphViewer = ({ model }) => {
         <div className="bg-gray-800 text-white p-2 flex justify-between items-center">
           <div>
             <span className="font-bold block">{node.label}</span>
-            <span className="text-xs text-gray-300 opacity-75">{node.group}</span>
+            <span className="text-xs text-gray-300 opacity-75">{node.group}{node.units ? ` · ${node.units}` : ''}</span>
+            {node.description && <span className="text-xs text-gray-300 block">{node.description}</span>}
           </div>
           <button 
             onClick={(e) => { e.stopPropagation(); setSelectedNode(null); }}
@@ -8,44 +8,25 @@ import {
   FaToolbox
 } from 'react-icons/fa';
 
-// Configuración de la UI
-export const SENSORES = [
-  {
-    id: 'T_sensor',
-    label: 'Sensor de Temperatura',
-    options: ['baja', 'normal', 'alta'],
-    icon: FaThermometerHalf,
-    color: 'text-red-500'
-  },
-  {
-    id: 'pH_sensor',
-    label: 'Sensor de pH',
-    options: ['acido', 'neutro', 'alcalino'],
-    icon: FaFlask,
-    color: 'text-purple-500'
-  },
-  {
-    id: 'Gas_sensor',
-    label: 'Sensor de Gas',
-    options: ['bajo', 'normal', 'alto'],
-    icon: FaFire,
-    color: 'text-orange-500'
-  },
-  {
-    id: 'Flow_sensor',
-    label: 'Sensor de Caudal',
-    options: ['bajo', 'normal', 'alto'],
-    icon: FaTint,
-    color: 'text-blue-500'
-  },
-  {
-    id: 'Presion_sensor',
-    label: 'Sensor de Presión',
-    options: ['baja', 'normal', 'alta'],
-    icon: FaTachometerAlt,
-    color: 'text-indigo-500'
-  },
-];
+// Configuración de la UI. La lista de sensores (etiqueta y estados) sale del modelo
+// con `get_node_names("observable")`; aquí solo queda el estilo de cada uno.
+const SENSOR_STYLES = {
+  T_sensor: { icon: FaThermometerHalf, color: 'text-red-500' },
+  pH_sensor: { icon: FaFlask, color: 'text-purple-500' },
+  Gas_sensor: { icon: FaFire, color: 'text-orange-500' },
+  Flow_sensor: { icon: FaTint, color: 'text-blue-500' },
+  Presion_sensor: { icon: FaTachometerAlt, color: 'text-indigo-500' },
+};
+
+const DEFAULT_SENSOR_STYLE = { icon: FaTachometerAlt, color: 'text-slate-500' };
+
+export const buildSensores = (model) =>
+  model.get_node_names('observable').map(node => ({
+    id: node.name,
+    label: node.label,
+    options: node.states,
+    ...(SENSOR_STYLES[node.name] ?? DEFAULT_SENSOR_STYLE),
+  }));
 
 export const OBJETIVOS = [
   {
@@ -1,103 +1,30 @@
-use std::collections::HashMap;
+#[cfg(test)]
 use suma_core::core::probability::bayes::BayesianNetwork;
-use suma_core::core::probability::bayes::BN_base::{BayesianNetworkBase, State};
+use crate::model::{ModelSpec, DEFAULT_MODEL_JSON};
 
 // Este módulo contiene la definición de la Red Bayesiana del Biodigestor.
+// Las CPTs viven en `models/biodigestor.json`; cambiarlas no requiere tocar código.
+pub(crate) fn default_model() -> Result<ModelSpec, String> {
+     ModelSpec::from_json(DEFAULT_MODEL_JSON)
+}
+
+// Red del modelo por defecto, sin metadatos (la usan los tests de los motores).
+#[cfg(test)]
 pub(crate) fn build_network_internal() -> Result<BayesianNetwork, String> {
-     let mut bn = BayesianNetwork::new();
-
-     // --- 1. Nodos raíz (sin padres) ---
-
-     let microbiano_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([(vec![], HashMap::from([("Bueno", 0.85), ("Degradado", 0.15)]))]);
-     bn.add_discrete_node("EstadoMicrobiano", vec![], vec!["Bueno", "Degradado"], microbiano_cpt)?;
-
-     let operativo_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([(vec![], HashMap::from([("Normal", 0.95), ("FallaMecanica", 0.03), ("Fuga", 0.02)]))]);
-     bn.add_discrete_node("EstadoOperativo", vec![], vec!["Normal", "FallaMecanica", "Fuga"], operativo_cpt)?;
-
-     // --- 2. Nodos físicos/intermedios ---
-
-     let temp_real_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Bueno"], HashMap::from([("Normal", 0.9), ("Baja", 0.05), ("Alta", 0.05)])),
-          (vec!["Degradado"], HashMap::from([("Baja", 0.6), ("Normal", 0.3), ("Alta", 0.1)])),
-     ]);
-     bn.add_discrete_node("TemperaturaReal", vec!["EstadoMicrobiano"], vec!["Baja","Normal","Alta"], temp_real_cpt)?;
-
-     let ph_real_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Bueno"], HashMap::from([("Neutro", 0.85), ("Acido", 0.1), ("Alcalino", 0.05)])),
-          (vec!["Degradado"], HashMap::from([("Acido", 0.6), ("Neutro", 0.3), ("Alcalino", 0.1)])),
-     ]);
-     bn.add_discrete_node("pHReal", vec!["EstadoMicrobiano"], vec!["Acido","Neutro","Alcalino"], ph_real_cpt)?;
-
-     let caudal_real_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Normal"], HashMap::from([("Normal", 0.9), ("Bajo", 0.05), ("Alto", 0.05)])),
-          (vec!["Fuga"], HashMap::from([("Alto", 0.7), ("Normal", 0.2), ("Bajo", 0.1)])),
-          (vec!["FallaMecanica"], HashMap::from([("Bajo", 0.8), ("Normal", 0.15), ("Alto", 0.05)])),
-     ]);
-     bn.add_discrete_node("CaudalReal", vec!["EstadoOperativo"], vec!["Bajo","Normal","Alto"], caudal_real_cpt)?;
-
-     let presion_real_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Normal"], HashMap::from([("Normal", 0.9), ("Baja", 0.05), ("Alta", 0.05)])),
-          (vec!["Fuga"], HashMap::from([("Baja", 0.6), ("Normal", 0.3), ("Alta", 0.1)])),
-          (vec!["FallaMecanica"], HashMap::from([("Alta", 0.7), ("Normal", 0.2), ("Baja", 0.1)])),
-     ]);
-     bn.add_discrete_node("PresionReal", vec!["EstadoOperativo"], vec!["Baja","Normal","Alta"], presion_real_cpt)?;
-
-     // --- 3. ProduccionGasReal ---
-     let prod_gas_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Bueno","Normal"], HashMap::from([("Normal",0.85),("Alta",0.10),("Baja",0.05)])),
-          (vec!["Bueno","Bajo"], HashMap::from([("Normal",0.5),("Alta",0.1),("Baja",0.4)])),
-          (vec!["Degradado","Normal"], HashMap::from([("Normal",0.2),("Alta",0.1),("Baja",0.7)])),
-          (vec!["Degradado","Bajo"], HashMap::from([("Normal",0.09),("Alta",0.01),("Baja",0.9)])),
-          (vec!["Bueno","Alto"], HashMap::from([("Normal",0.7),("Alta",0.25),("Baja",0.05)])),
-          (vec!["Degradado","Alto"], HashMap::from([("Normal",0.1),("Alta",0.2),("Baja",0.7)])),
-     ]);
-     bn.add_discrete_node("ProduccionGasReal", vec!["EstadoMicrobiano","CaudalReal"], vec!["Baja","Normal","Alta"], prod_gas_cpt)?;
-
-     // --- 4. Nodos sensores ---
-     let t_sensor_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Alta"], HashMap::from([("alta",0.92),("normal",0.07),("baja",0.01)])),
-          (vec!["Normal"], HashMap::from([("normal",0.9),("baja",0.05),("alta",0.05)])),
-          (vec!["Baja"], HashMap::from([("baja",0.95),("normal",0.04),("alta",0.01)])),
-     ]);
-     bn.add_discrete_node("T_sensor", vec!["TemperaturaReal"], vec!["baja","normal","alta"], t_sensor_cpt)?;
-
-     let ph_sensor_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Neutro"], HashMap::from([("neutro",0.9),("acido",0.05),("alcalino",0.05)])),
-          (vec!["Acido"], HashMap::from([("acido",0.9),("neutro",0.05),("alcalino",0.05)])),
-          (vec!["Alcalino"], HashMap::from([("alcalino",0.9),("neutro",0.05),("acido",0.05)])),
-     ]);
-     bn.add_discrete_node("pH_sensor", vec!["pHReal"], vec!["acido","neutro","alcalino"], ph_sensor_cpt)?;
-
-     let flow_sensor_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Bajo"], HashMap::from([("bajo",0.95),("normal",0.04),("alto",0.01)])),
-          (vec!["Normal"], HashMap::from([("normal",0.9),("bajo",0.05),("alto",0.05)])),
-          (vec!["Alto"], HashMap::from([("alto",0.92),("normal",0.06),("bajo",0.02)])),
-     ]);
-     bn.add_discrete_node("Flow_sensor", vec!["CaudalReal"], vec!["bajo","normal","alto"], flow_sensor_cpt)?;
-
-     let gas_sensor_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Baja"], HashMap::from([("bajo",0.95),("normal",0.04),("alto",0.01)])),
-          (vec!["Normal"], HashMap::from([("normal",0.9),("bajo",0.05),("alto",0.05)])),
-          (vec!["Alta"], HashMap::from([("alto",0.92),("normal",0.06),("bajo",0.02)])),
-     ]);
-     bn.add_discrete_node("Gas_sensor", vec!["ProduccionGasReal"], vec!["bajo","normal","alto"], gas_sensor_cpt)?;
-
-     let presion_sensor_cpt: HashMap<Vec<&str>, HashMap<&str, f64>> = HashMap::from([
-          (vec!["Alta"], HashMap::from([("alta",0.92),("normal",0.06),("baja",0.02)])),
-          (vec!["Normal"], HashMap::from([("normal",0.9),("baja",0.05),("alta",0.05)])),
-          (vec!["Baja"], HashMap::from([("baja",0.95),("normal",0.04),("alta",0.01)])),
-     ]);
-     bn.add_discrete_node("Presion_sensor", vec!["PresionReal"], vec!["baja","normal","alta"], presion_sensor_cpt)?;
-
-     Ok(bn)
+     default_model()?.build_network()
 }
 
 
 #[cfg(test)]
 mod tests {
      use std::collections::HashMap;
-     use serde_json::{self, Value};
+     use serde_json::{self, json, Value};
      use suma_core::core::probability::bayes::BN_base::{BayesianNetworkBase, State};
+     use suma_core::core::probability::bayes::CPT;
+     use crate::inference::InferenceOptions;
+     use crate::inference::sampling::likelihood_weighting;
+     use crate::inference::tables::NetworkTables;
+     use crate::inference::variable_elimination::variable_elimination;
      const TOLERANCE: f64 = 1e-6;
 
      use super::*;
@@ -156,7 +83,9 @@ mod tests {
           evidence.insert(ph_sensor_id, State::Value("neutro".to_string())); 
 
           let target_id = bn.get_id_from_name("EstadoMicrobiano").expect("Node EstadoMicrobiano not found");
-          let distribution = bn.likelihood_weighting_sampling(&evidence, target_id, 10_000);
+          let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+          let options = InferenceOptions { samples: 10_000, seed: Some(2024), ..InferenceOptions::default() };
+          let distribution = likelihood_weighting(&tables, &evidence, target_id, &options).expect("Sampling failed");
 
           assert!(!distribution.is_empty(), "FALLO CRÍTICO: Inferencia simple devuelve distribución vacía. La lógica de rechazo está rota.");
           
@@ -183,7 +112,9 @@ mod tests {
 
         // Query el nodo "PresionReal" (hijo del nodo raíz con evidencia)
         let query_id = bn.get_id_from_name("PresionReal").expect("Node PresionReal not found");
-        let distribution = bn.likelihood_weighting_sampling(&evidence, query_id, 1000);
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let options = InferenceOptions { samples: 1000, seed: Some(2024), ..InferenceOptions::default() };
+        let distribution = likelihood_weighting(&tables, &evidence, query_id, &options).expect("Sampling failed");
 
         assert!(!distribution.is_empty(), "FALLO DE SANIDAD: La inferencia con evidencia en un nodo raíz devuelve distribución vacía. ERROR SEVERO EN LOGICA DE MUESTREO.");
         
@@ -197,6 +128,33 @@ mod tests {
         assert!(prob_baja > 0.5, "Bajo 'Fuga', PresionReal debe tener una alta probabilidad de ser 'Baja'. Prob Baja: {}", prob_baja);
     }
 
+    #[test]
+    fn test_exact_inference_all_sensors() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+
+        // Mismo escenario que test_complex_inference_high_samples, pero exacto
+        let mut evidence: HashMap<usize, State> = HashMap::new();
+        for (sensor, value) in [("T_sensor", "normal"), ("pH_sensor", "neutro"), ("Flow_sensor", "normal"), ("Gas_sensor", "normal"), ("Presion_sensor", "normal")] {
+            let id = bn.get_id_from_name(sensor).expect("Sensor not found");
+            evidence.insert(id, State::Value(value.to_string()));
+        }
+
+        let target_id = bn.get_id_from_name("EstadoMicrobiano").expect("Node EstadoMicrobiano not found");
+        let exact = variable_elimination(&tables, &evidence, target_id).expect("Exact inference failed");
+
+        let sum_probabilities: f64 = exact.values().sum();
+        assert!((sum_probabilities - 1.0).abs() < TOLERANCE, "Probabilidades exactas no suman a 1.0. Suma: {}", sum_probabilities);
+
+        // Dos ejecuciones con la misma evidencia deben dar exactamente lo mismo
+        let again = variable_elimination(&tables, &evidence, target_id).expect("Exact inference failed");
+        assert_eq!(exact, again, "La inferencia exacta debe ser determinista");
+
+        let prob_bueno = exact.get(&State::Value("Bueno".to_string())).copied().unwrap_or(0.0);
+        println!("Inferencia exacta (EstadoMicrobiano | Sensores Normales): {:?}", exact);
+        assert!(prob_bueno > 0.9, "Con todos los sensores normales, 'Bueno' debería dominar. Prob Bueno: {}", prob_bueno);
+    }
+
     #[test]
     fn debug_cpt_structure_and_data() {
         // 1. Construir la red
@@ -213,7 +171,7 @@ mod tests {
             let cpt = bn.get_node_cpt_by_id(&node_id);
             
             // 3. Serializar a Value para poder navegar el JSON en el test
-            let json_value = serde_json::to_value(&cpt).expect("Failed to serialize to Value");
+            let json_value = cpt.map_or(Value::Null, cpt_to_value);
             
             println!("\n📍 Nodo: {} (ID: {})", node_name, node_id);
             
@@ -235,7 +193,28 @@ mod tests {
         }
     }
 
+    // serde_json no admite `State` como clave de mapa, así que armamos el JSON a mano
+    // con el mismo formato de claves que `serialize_complex_key` de suma_core.
+    fn cpt_to_value(cpt: &CPT) -> Value {
+        match cpt {
+            CPT::Discrete(discrete) => {
+                let table: serde_json::Map<String, Value> = discrete.table.iter()
+                    .map(|(parents, distribution)| {
+                        let key = serde_json::to_string(parents).unwrap();
+                        let row: serde_json::Map<String, Value> = distribution.iter()
+                            .map(|(state, p)| (crate::state_to_string(state.clone()), json!(p)))
+                            .collect();
+                        (key, Value::Object(row))
+                    })
+                    .collect();
+                json!({ "Discrete": { "node_possible_values": discrete.node_possible_values, "table": table } })
+            }
+            CPT::Binary(binary) => json!({ "Binary": { "table": binary.table } }),
+        }
+    }
+
     // Función auxiliar para revisar el contenido de la tabla
+    #[allow(unused_variables)]
     fn check_table_content(tipo: &str, data: &Value, node_name: &str, found_empty: &mut bool) {
         // Verificar estados posibles
         if let Some(values) = data.get("node_possible_values") {
@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use wasm_bindgen::JsValue;
+
+use crate::evidence::UnknownState;
+use crate::validation::Diagnostic;
+
+// Errores de la API pública. En JS llegan como `{ kind, message, details }` para que
+// el frontend pueda distinguir el tipo de error sin interpretar el mensaje.
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BnError {
+    /// El nodo no existe en la red.
+    UnknownNode { node: String },
+    /// Estado de evidencia que el nodo no declara.
+    UnknownState(UnknownState),
+    /// La evidencia tiene probabilidad cero bajo el modelo. `conflict` es un subconjunto
+    /// mínimo de la evidencia que ya es imposible por sí solo.
+    ImpossibleEvidence { evidence: BTreeMap<String, String>, conflict: BTreeMap<String, String> },
+    /// El modelo no se pudo leer o no pasa `validate()`; `diagnostics` puede estar vacío
+    /// si el problema es de formato.
+    InvalidModel { message: String, diagnostics: Vec<Diagnostic> },
+    /// Tabla de casos ilegible o con estados que el modelo no declara.
+    InvalidData { message: String },
+    /// Parámetros de la consulta incorrectos (lista vacía, condiciones contradictorias...).
+    InvalidQuery { message: String },
+    /// Un motor de muestreo terminó sin ninguna muestra válida (p. ej. todos los pesos
+    /// nulos con evidencia muy improbable). Otro algoritmo o más muestras pueden servir.
+    SamplingFailed { message: String },
+    SerializationError { message: String },
+}
+
+impl BnError {
+    pub fn unknown_node(node: &str) -> BnError {
+        BnError::UnknownNode { node: node.to_string() }
+    }
+
+    pub fn impossible_evidence(evidence: &HashMap<String, String>, conflict: BTreeMap<String, String>) -> BnError {
+        BnError::ImpossibleEvidence { evidence: evidence.iter().map(|(k, v)| (k.clone(), v.clone())).collect(), conflict }
+    }
+
+    pub fn invalid_model(message: impl Into<String>) -> BnError {
+        BnError::InvalidModel { message: message.into(), diagnostics: Vec::new() }
+    }
+
+    pub fn invalid_data(message: impl Into<String>) -> BnError {
+        BnError::InvalidData { message: message.into() }
+    }
+
+    pub fn invalid_query(message: impl Into<String>) -> BnError {
+        BnError::InvalidQuery { message: message.into() }
+    }
+
+    pub fn sampling_failed(message: impl Into<String>) -> BnError {
+        BnError::SamplingFailed { message: message.into() }
+    }
+
+    pub fn serialization(error: impl fmt::Display) -> BnError {
+        BnError::SerializationError { message: format!("Serialization error: {}", error) }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BnError::UnknownNode { .. } => "UnknownNode",
+            BnError::UnknownState(_) => "UnknownState",
+            BnError::ImpossibleEvidence { .. } => "ImpossibleEvidence",
+            BnError::InvalidModel { .. } => "InvalidModel",
+            BnError::InvalidData { .. } => "InvalidData",
+            BnError::InvalidQuery { .. } => "InvalidQuery",
+            BnError::SamplingFailed { .. } => "SamplingFailed",
+            BnError::SerializationError { .. } => "SerializationError",
+        }
+    }
+}
+
+impl fmt::Display for BnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BnError::UnknownNode { node } => write!(f, "Node not found: {}", node),
+            BnError::UnknownState(error) => write!(f, "{}", error),
+            BnError::ImpossibleEvidence { conflict, .. } => {
+                let readings: Vec<String> = conflict.iter().map(|(node, state)| format!("{}={}", node, state)).collect();
+                write!(f, "Evidence has zero probability under the model (conflict: {})", readings.join(", "))
+            }
+            BnError::InvalidModel { message, .. }
+            | BnError::InvalidData { message }
+            | BnError::InvalidQuery { message }
+            | BnError::SamplingFailed { message }
+            | BnError::SerializationError { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for BnError {}
+
+impl From<UnknownState> for BnError {
+    fn from(error: UnknownState) -> BnError {
+        BnError::UnknownState(error)
+    }
+}
+
+impl Serialize for BnError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("BnError", 3)?;
+        error.serialize_field("kind", self.kind())?;
+        error.serialize_field("message", &self.to_string())?;
+        match self {
+            BnError::UnknownNode { node } => error.serialize_field("details", &BTreeMap::from([("node", node)]))?,
+            BnError::UnknownState(state) => error.serialize_field("details", state)?,
+            BnError::ImpossibleEvidence { evidence, conflict } => {
+                error.serialize_field("details", &BTreeMap::from([("evidence", evidence), ("conflict", conflict)]))?
+            }
+            BnError::InvalidModel { diagnostics, .. } => {
+                error.serialize_field("details", &BTreeMap::from([("diagnostics", diagnostics)]))?
+            }
+            BnError::InvalidData { .. }
+            | BnError::InvalidQuery { .. }
+            | BnError::SamplingFailed { .. }
+            | BnError::SerializationError { .. } => {
+                error.serialize_field("details", &Option::<()>::None)?
+            }
+        }
+        error.end()
+    }
+}
+
+impl From<BnError> for JsValue {
+    fn from(error: BnError) -> JsValue {
+        // Si ni siquiera el error se puede serializar, al menos llega el mensaje
+        error.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .unwrap_or_else(|_| JsValue::from_str(&error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_serializes_kind_message_and_details() {
+        let error = BnError::UnknownState(UnknownState::new("T_sensor", "Normal", vec!["baja".into(), "normal".into()]));
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "UnknownState");
+        assert_eq!(json["message"], error.to_string());
+        assert_eq!(json["details"]["suggestion"], "normal");
+
+        let json = serde_json::to_value(BnError::unknown_node("Fuga")).unwrap();
+        assert_eq!((json["kind"].as_str(), json["details"]["node"].as_str()), (Some("UnknownNode"), Some("Fuga")));
+        assert!(serde_json::to_value(BnError::invalid_query("x")).unwrap()["details"].is_null());
+    }
+}
@@ -0,0 +1,90 @@
+use std::fmt;
+use serde::Serialize;
+
+// Comprobación de la evidencia contra los estados declarados de cada nodo. Un estado
+// mal escrito ("Normal" en lugar de "normal") no debe llegar a la inferencia.
+
+/// Estado de evidencia que el nodo no declara.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UnknownState {
+    pub node: String,
+    pub value: String,
+    /// Estados válidos del nodo, en el orden declarado.
+    pub valid: Vec<String>,
+    /// Estado válido más parecido, si lo hay.
+    pub suggestion: Option<String>,
+}
+
+impl UnknownState {
+    pub fn new(node: &str, value: &str, valid: Vec<String>) -> UnknownState {
+        let suggestion = suggest(value, &valid);
+        UnknownState { node: node.to_string(), value: value.to_string(), valid, suggestion }
+    }
+}
+
+impl fmt::Display for UnknownState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown state '{}' for node '{}' (valid: {})", self.value, self.node, self.valid.join(", "))?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean '{}'?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+// Primero una coincidencia exacta sin distinguir mayúsculas; si no, el estado más
+// cercano por distancia de edición (sin mayúsculas), con un máximo de 2 cambios.
+fn suggest(value: &str, valid: &[String]) -> Option<String> {
+    let lower = value.to_lowercase();
+    if let Some(state) = valid.iter().find(|s| s.to_lowercase() == lower) {
+        return Some(state.clone());
+    }
+    valid.iter()
+        .map(|s| (edit_distance(&lower, &s.to_lowercase()), s))
+        .filter(|(distance, _)| *distance <= 2)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, s)| s.clone())
+}
+
+// Distancia de Levenshtein por caracteres.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn states(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_suggestion_ignores_case_then_uses_edit_distance() {
+        let valid = states(&["baja", "normal", "alta"]);
+        assert_eq!(suggest("Normal", &valid).as_deref(), Some("normal"));
+        assert_eq!(suggest("ALTA", &valid).as_deref(), Some("alta"));
+        assert_eq!(suggest("nromal", &valid).as_deref(), Some("normal"));
+        assert_eq!(suggest("Bajo", &valid).as_deref(), Some("baja"));
+        assert_eq!(suggest("fuga", &valid), None);
+    }
+
+    #[test]
+    fn test_message_names_node_value_and_options() {
+        let error = UnknownState::new("T_sensor", "Normal", states(&["baja", "normal", "alta"]));
+        assert_eq!(
+            error.to_string(),
+            "Unknown state 'Normal' for node 'T_sensor' (valid: baja, normal, alta); did you mean 'normal'?"
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use std::fmt::Write;
+
+use super::{apply_property, check_identifier, cpt_row, escape, metadata_properties, parent_configurations, row_values, tokenize, Token};
+use crate::model::{CptRow, ModelSpec, NodeMetadata, NodeSpec};
+
+// Interchange BIF (formato de texto de JavaBayes, pgmpy, bnlearn...):
+//
+// network Biodigestor { }
+// variable EstadoMicrobiano { type discrete [ 2 ] { Bueno, Degradado }; }
+// probability ( TemperaturaReal | EstadoMicrobiano ) {
+//   (Bueno) 0.05, 0.9, 0.05;
+//   (Degradado) 0.6, 0.3, 0.1;
+// }
+//
+// Además de las filas explícitas se aceptan `table` (todas las filas seguidas, con el
+// último padre variando más rápido) y `default` (filas no listadas).
+
+impl ModelSpec {
+    pub fn from_bif(text: &str) -> Result<ModelSpec, String> {
+        let mut parser = Parser { tokens: tokenize(text, "//")?, pos: 0 };
+        let mut spec = ModelSpec { name: String::new(), description: None, nodes: Vec::new() };
+        let mut probabilities: Vec<(String, Vec<String>, Vec<Entry>)> = Vec::new();
+
+        while let Some(token) = parser.next() {
+            match token {
+                Token::Word(w) if w == "network" => {
+                    spec.name = parser.name()?;
+                    let mut metadata = NodeMetadata::default();
+                    parser.block_properties(&mut metadata)?;
+                    spec.description = metadata.description;
+                }
+                Token::Word(w) if w == "variable" => spec.nodes.push(parser.variable()?),
+                Token::Word(w) if w == "probability" => probabilities.push(parser.probability()?),
+                other => return Err(format!("BIF: unexpected {:?}", other)),
+            }
+        }
+
+        for (child, parents, entries) in probabilities {
+            let index = spec.nodes.iter().position(|n| n.name == child)
+                .ok_or_else(|| format!("BIF: probability for undeclared variable '{}'", child))?;
+            spec.nodes[index].parents = parents;
+            let rows = table_rows(&spec, &spec.nodes[index], entries)?;
+            spec.nodes[index].cpt = rows;
+        }
+        Ok(spec)
+    }
+
+    pub fn to_bif(&self) -> Result<String, String> {
+        let mut out = String::new();
+        writeln!(out, "network {} {{", self.name.replace(char::is_whitespace, "_")).unwrap();
+        if let Some(description) = &self.description {
+            writeln!(out, "  property \"description = {}\" ;", escape(description)).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+
+        // Los nombres de nodos y estados van sin comillas: deben ser palabras de BIF
+        for node in &self.nodes {
+            check_identifier("BIF", "//", "Node", &node.name)?;
+            for state in &node.states {
+                check_identifier("BIF", "//", &format!("State of '{}'", node.name), state)?;
+            }
+        }
+
+        for node in &self.nodes {
+            writeln!(out, "variable {} {{", node.name).unwrap();
+            writeln!(out, "  type discrete [ {} ] {{ {} }};", node.states.len(), node.states.join(", ")).unwrap();
+            for property in metadata_properties(&node.metadata) {
+                writeln!(out, "  property \"{}\" ;", escape(&property)).unwrap();
+            }
+            writeln!(out, "}}").unwrap();
+        }
+
+        for node in &self.nodes {
+            if node.parents.is_empty() {
+                writeln!(out, "probability ( {} ) {{", node.name).unwrap();
+                if let Some(values) = row_values(node, &[]) {
+                    writeln!(out, "  table {};", join_values(&values)).unwrap();
+                }
+            } else {
+                writeln!(out, "probability ( {} | {} ) {{", node.name, node.parents.join(", ")).unwrap();
+                for given in parent_configurations(self, node)? {
+                    if let Some(values) = row_values(node, &given) {
+                        writeln!(out, "  ({}) {};", given.join(", "), join_values(&values)).unwrap();
+                    }
+                }
+            }
+            writeln!(out, "}}").unwrap();
+        }
+        Ok(out)
+    }
+}
+
+// `{}` en f64 escribe la representación más corta que vuelve al mismo valor al parsear.
+fn join_values(values: &[f64]) -> String {
+    values.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+enum Entry {
+    Table(Vec<f64>),
+    Default(Vec<f64>),
+    Row(Vec<String>, Vec<f64>),
+}
+
+fn table_rows(spec: &ModelSpec, node: &NodeSpec, entries: Vec<Entry>) -> Result<Vec<CptRow>, String> {
+    let configurations = parent_configurations(spec, node)?;
+    let card = node.states.len();
+    let mut rows: Vec<Option<Vec<f64>>> = vec![None; configurations.len()];
+    let mut default = None;
+
+    for entry in entries {
+        match entry {
+            Entry::Table(values) => {
+                if values.len() != card * configurations.len() {
+                    return Err(format!("BIF: table of '{}' has {} values, expected {}", node.name, values.len(), card * configurations.len()));
+                }
+                for (slot, chunk) in rows.iter_mut().zip(values.chunks(card)) {
+                    *slot = Some(chunk.to_vec());
+                }
+            }
+            Entry::Default(values) => default = Some(values),
+            Entry::Row(given, values) => {
+                let slot = configurations.iter().position(|c| *c == given)
+                    .ok_or_else(|| format!("BIF: '{}' has a row for unknown parent states {:?}", node.name, given))?;
+                rows[slot] = Some(values);
+            }
+        }
+    }
+
+    let mut cpt = Vec::new();
+    for (given, values) in configurations.into_iter().zip(rows) {
+        let Some(values) = values.or_else(|| default.clone()) else {
+            continue;
+        };
+        if values.len() != card {
+            return Err(format!("BIF: row {:?} of '{}' has {} values, expected {}", given, node.name, values.len(), card));
+        }
+        cpt.extend(cpt_row(&node.states, given, &values));
+    }
+    Ok(cpt)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Punct(c)) if c == punct => Ok(()),
+            other => Err(format!("BIF: expected '{}', found {:?}", punct, other)),
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(w)) | Some(Token::Text(w)) => Ok(w),
+            other => Err(format!("BIF: expected a name, found {:?}", other)),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        let word = self.name()?;
+        word.parse().map_err(|_| format!("BIF: '{}' is not a probability", word))
+    }
+
+    // Números separados por comas o espacios hasta `;`.
+    fn numbers(&mut self) -> Result<Vec<f64>, String> {
+        let mut values = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Punct(';')) => {
+                    self.next();
+                    return Ok(values);
+                }
+                Some(Token::Punct(',')) => {
+                    self.next();
+                }
+                _ => values.push(self.number()?),
+            }
+        }
+    }
+
+    // Lista de nombres separada por comas hasta el cierre `close`.
+    fn names_until(&mut self, close: char) -> Result<Vec<String>, String> {
+        let mut names = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Punct(c)) if *c == close => {
+                    self.next();
+                    return Ok(names);
+                }
+                Some(Token::Punct(',')) => {
+                    self.next();
+                }
+                _ => names.push(self.name()?),
+            }
+        }
+    }
+
+    fn skip_statement(&mut self) -> Result<(), String> {
+        loop {
+            match self.next() {
+                Some(Token::Punct(';')) => return Ok(()),
+                Some(_) => {}
+                None => return Err("BIF: missing ';'".to_string()),
+            }
+        }
+    }
+
+    // `{ property "clave = valor"; ... }`, ignorando cualquier otra sentencia.
+    fn block_properties(&mut self, metadata: &mut NodeMetadata) -> Result<(), String> {
+        self.expect('{')?;
+        loop {
+            match self.next() {
+                Some(Token::Punct('}')) => return Ok(()),
+                Some(Token::Word(w)) if w == "property" => {
+                    if let Some(Token::Text(property)) = self.peek().cloned() {
+                        apply_property(metadata, &property);
+                    }
+                    self.skip_statement()?;
+                }
+                Some(_) => self.skip_statement()?,
+                None => return Err("BIF: unterminated block".to_string()),
+            }
+        }
+    }
+
+    fn variable(&mut self) -> Result<NodeSpec, String> {
+        let name = self.name()?;
+        let mut states = Vec::new();
+        let mut metadata = NodeMetadata::default();
+
+        self.expect('{')?;
+        loop {
+            match self.next() {
+                Some(Token::Punct('}')) => break,
+                Some(Token::Word(w)) if w == "type" => {
+                    // type discrete [ n ] { a, b, c };
+                    match self.name()?.as_str() {
+                        "discrete" => {}
+                        other => return Err(format!("BIF: variable '{}' has unsupported type '{}'", name, other)),
+                    }
+                    self.expect('[')?;
+                    let declared = self.number()? as usize;
+                    self.expect(']')?;
+                    self.expect('{')?;
+                    states = self.names_until('}')?;
+                    self.expect(';')?;
+                    if states.len() != declared {
+                        return Err(format!("BIF: variable '{}' declares {} states but lists {}", name, declared, states.len()));
+                    }
+                }
+                Some(Token::Word(w)) if w == "property" => {
+                    if let Some(Token::Text(property)) = self.peek().cloned() {
+                        apply_property(&mut metadata, &property);
+                    }
+                    self.skip_statement()?;
+                }
+                Some(_) => self.skip_statement()?,
+                None => return Err(format!("BIF: unterminated variable '{}'", name)),
+            }
+        }
+
+        Ok(NodeSpec { name, states, parents: Vec::new(), cpt: Vec::new(), metadata })
+    }
+
+    fn probability(&mut self) -> Result<(String, Vec<String>, Vec<Entry>), String> {
+        self.expect('(')?;
+        let child = self.name()?;
+        let parents = match self.next() {
+            Some(Token::Punct(')')) => Vec::new(),
+            Some(Token::Punct('|')) => self.names_until(')')?,
+            other => return Err(format!("BIF: expected '|' or ')', found {:?}", other)),
+        };
+
+        let mut entries = Vec::new();
+        self.expect('{')?;
+        loop {
+            match self.next() {
+                Some(Token::Punct('}')) => break,
+                Some(Token::Word(w)) if w == "table" => entries.push(Entry::Table(self.numbers()?)),
+                Some(Token::Word(w)) if w == "default" => entries.push(Entry::Default(self.numbers()?)),
+                Some(Token::Punct('(')) => {
+                    let given = self.names_until(')')?;
+                    entries.push(Entry::Row(given, self.numbers()?));
+                }
+                Some(_) => self.skip_statement()?,
+                None => return Err(format!("BIF: unterminated probability block for '{}'", child)),
+            }
+        }
+        Ok((child, parents, entries))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{build_network_internal, default_model};
+    use crate::formats::tests::assert_same_network;
+
+    #[test]
+    fn test_bif_round_trip_is_bit_exact() {
+        let spec = default_model().unwrap();
+        let bif = spec.to_bif().unwrap();
+
+        let parsed = ModelSpec::from_bif(&bif).expect("Exported BIF must parse");
+        assert_same_network(&build_network_internal().unwrap(), &parsed.build_network().unwrap());
+        assert_eq!(parsed.metadata()["CaudalReal"].units.as_deref(), Some("m³/h"));
+
+        // Exportar lo importado produce el mismo texto
+        assert_eq!(parsed.to_bif().unwrap(), bif);
+    }
+
+    #[test]
+    fn test_bif_table_default_and_comments() {
+        let bif = r#"
+            // Red de ejemplo
+            network "Lluvia" { property "description = ejemplo" ; }
+            variable Lluvia { type discrete [ 2 ] { si, no }; }
+            variable Pasto { type discrete [2] { mojado, seco }; property "label = Pasto"; }
+            /* CPTs */
+            probability ( Lluvia ) { table 0.2 0.8 ; }
+            probability ( Pasto | Lluvia ) {
+              (si) 0.9, 0.1;
+              default 0.3, 0.7;
+            }
+        "#;
+        let spec = ModelSpec::from_bif(bif).expect("BIF must parse");
+        assert_eq!(spec.name, "Lluvia");
+        assert_eq!(spec.description.as_deref(), Some("ejemplo"));
+        let pasto = &spec.nodes[1];
+        assert_eq!(pasto.cpt.len(), 2);
+        assert_eq!(pasto.cpt[1].given, vec!["no"]);
+        assert_eq!(pasto.cpt[1].probabilities["seco"], 0.7);
+        assert_eq!(pasto.metadata.label.as_deref(), Some("Pasto"));
+        assert!(spec.build_network().is_ok());
+
+        assert!(ModelSpec::from_bif("variable X { type discrete [ 3 ] { a, b }; }").is_err());
+    }
+
+    #[test]
+    fn test_bif_export_rejects_invalid_names_and_escapes_quotes() {
+        let mut spec = default_model().unwrap();
+        spec.nodes[0].metadata.description = Some(r#"Dice "hola" en C:\datos"#.to_string());
+        let parsed = ModelSpec::from_bif(&spec.to_bif().unwrap()).unwrap();
+        assert_eq!(parsed.nodes[0].metadata.description, spec.nodes[0].metadata.description);
+
+        let mut spaced = default_model().unwrap();
+        spaced.nodes[0].name = "Estado Microbiano".to_string();
+        assert!(spaced.to_bif().unwrap_err().contains("Estado Microbiano"));
+        let mut listed = default_model().unwrap();
+        listed.nodes[1].states[0] = "a,b".to_string();
+        assert!(listed.to_bif().is_err());
+        let mut piped = default_model().unwrap();
+        piped.nodes[1].states[0] = "a|b".to_string();
+        assert!(piped.to_bif().is_err());
+    }
+}
@@ -0,0 +1,372 @@
+use std::fmt::Write;
+
+use super::{check_identifier, cpt_row, escape, parent_configurations, parse_position, row_values, tokenize, Token};
+use crate::model::{ModelSpec, NodeMetadata, NodeRole, NodeSpec};
+
+// Formato .net de Hugin (también lo leen GeNIe, SamIam y pgmpy):
+//
+// node TemperaturaReal
+// {
+//     label = "Temperatura real";
+//     position = (120 80);
+//     states = ("Baja" "Normal" "Alta");
+// }
+// potential ( TemperaturaReal | EstadoMicrobiano )
+// {
+//     data = (( 0.05 0.9 0.05 )    %  EstadoMicrobiano=Bueno
+//             ( 0.6 0.3 0.1 ));    %  EstadoMicrobiano=Degradado
+// }
+//
+// En `data` el primer padre es el más externo y el estado del nodo el más interno.
+// Solo se admiten nodos de azar discretos; `description` y `units` se guardan como
+// atributos de usuario, que Hugin conserva.
+
+impl ModelSpec {
+    pub fn from_hugin(text: &str) -> Result<ModelSpec, String> {
+        let mut parser = Parser { tokens: tokenize(text, "%")?, pos: 0 };
+        let mut spec = ModelSpec { name: String::new(), description: None, nodes: Vec::new() };
+        let mut potentials: Vec<(String, Vec<String>, Vec<f64>)> = Vec::new();
+
+        while let Some(token) = parser.next() {
+            let Token::Word(keyword) = token else {
+                return Err(format!("Hugin: unexpected {:?}", token));
+            };
+            match keyword.as_str() {
+                "net" => {
+                    for (key, value) in parser.attributes()? {
+                        match key.as_str() {
+                            "name" | "label" if spec.name.is_empty() => spec.name = value.text(),
+                            "description" => spec.description = Some(value.text()),
+                            _ => {}
+                        }
+                    }
+                }
+                "discrete" | "node" => {
+                    if keyword == "discrete" {
+                        parser.keyword("node")?;
+                    }
+                    spec.nodes.push(parser.node()?);
+                }
+                "potential" => potentials.push(parser.potential()?),
+                other => return Err(format!("Hugin: unsupported declaration '{}' (only discrete chance nodes)", other)),
+            }
+        }
+
+        for (child, parents, values) in potentials {
+            let index = spec.nodes.iter().position(|n| n.name == child)
+                .ok_or_else(|| format!("Hugin: potential for undeclared node '{}'", child))?;
+            spec.nodes[index].parents = parents;
+
+            let node = &spec.nodes[index];
+            let configurations = parent_configurations(&spec, node)?;
+            let card = node.states.len();
+            if values.len() != card * configurations.len() {
+                return Err(format!("Hugin: data of '{}' has {} values, expected {}", child, values.len(), card * configurations.len()));
+            }
+            let cpt = configurations.into_iter().zip(values.chunks(card))
+                .filter_map(|(given, row)| cpt_row(&node.states, given, row))
+                .collect();
+            spec.nodes[index].cpt = cpt;
+        }
+        Ok(spec)
+    }
+
+    pub fn to_hugin(&self) -> Result<String, String> {
+        // Los nombres de nodos van sin comillas en `node` y `potential` (los estados sí las llevan)
+        for node in &self.nodes {
+            check_identifier("Hugin", "%", "Node", &node.name)?;
+        }
+
+        let mut out = String::new();
+        writeln!(out, "net\n{{").unwrap();
+        writeln!(out, "    name = \"{}\";", escape(&self.name)).unwrap();
+        if let Some(description) = &self.description {
+            writeln!(out, "    description = \"{}\";", escape(description)).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+
+        for node in &self.nodes {
+            writeln!(out, "\nnode {}\n{{", node.name).unwrap();
+            let metadata = &node.metadata;
+            if let Some(label) = &metadata.label {
+                writeln!(out, "    label = \"{}\";", escape(label)).unwrap();
+            }
+            if let Some(role) = metadata.role {
+                writeln!(out, "    role = \"{}\";", role.as_str()).unwrap();
+            }
+            if let Some([x, y]) = metadata.position {
+                writeln!(out, "    position = ({} {});", x, y).unwrap();
+            }
+            let states: Vec<String> = node.states.iter().map(|s| format!("\"{}\"", escape(s))).collect();
+            writeln!(out, "    states = ({});", states.join(" ")).unwrap();
+            if let Some(description) = &metadata.description {
+                writeln!(out, "    description = \"{}\";", escape(description)).unwrap();
+            }
+            if let Some(units) = &metadata.units {
+                writeln!(out, "    units = \"{}\";", escape(units)).unwrap();
+            }
+            // Una descripción por estado, en el orden de `states` ("" si no tiene)
+            if !metadata.state_descriptions.is_empty() {
+                let descriptions: Vec<String> = node.states.iter()
+                    .map(|s| format!("\"{}\"", escape(metadata.state_descriptions.get(s).map_or("", String::as_str))))
+                    .collect();
+                writeln!(out, "    state_descriptions = ({});", descriptions.join(" ")).unwrap();
+            }
+            writeln!(out, "}}").unwrap();
+        }
+
+        for node in &self.nodes {
+            if node.parents.is_empty() {
+                writeln!(out, "\npotential ( {} )\n{{", node.name).unwrap();
+            } else {
+                writeln!(out, "\npotential ( {} | {} )\n{{", node.name, node.parents.join(" ")).unwrap();
+            }
+            // Las filas no definidas se escriben como ceros (y se descartan al importar)
+            let rows: Vec<(Vec<String>, Vec<f64>)> = parent_configurations(self, node)?.into_iter()
+                .map(|given| {
+                    let values = row_values(node, &given).unwrap_or_else(|| vec![0.0; node.states.len()]);
+                    (given, values)
+                })
+                .collect();
+            let cards: Vec<usize> = node.parents.iter()
+                .map(|p| self.nodes.iter().find(|n| &n.name == p).map_or(1, |n| n.states.len()))
+                .collect();
+            writeln!(out, "    data = {}", nested_data(node, &rows, &cards)).unwrap();
+            writeln!(out, "}}").unwrap();
+        }
+        Ok(out)
+    }
+}
+
+// Paréntesis anidados por padre, una fila por línea con un comentario que indica los
+// padres. Incluye el `;` final, que debe ir antes del último comentario.
+fn nested_data(node: &NodeSpec, rows: &[(Vec<String>, Vec<f64>)], cards: &[usize]) -> String {
+    let mut out = String::new();
+    for (i, (given, values)) in rows.iter().enumerate() {
+        // Paréntesis que se abren antes de esta fila y se cierran después
+        let mut opening = 1;
+        let mut closing = 1;
+        let mut block = 1;
+        for card in cards.iter().rev() {
+            block *= card;
+            if i % block == 0 {
+                opening += 1;
+            }
+            if (i + 1) % block == 0 {
+                closing += 1;
+            }
+        }
+        if i > 0 {
+            out.push_str("\n            ");
+        }
+        let numbers: Vec<String> = values.iter().map(|p| p.to_string()).collect();
+        write!(out, "{}( {} ){}", "(".repeat(opening - 1), numbers.join(" "), ")".repeat(closing - 1)).unwrap();
+        if i + 1 == rows.len() {
+            out.push(';');
+        }
+        if !given.is_empty() {
+            let labels: Vec<String> = node.parents.iter().zip(given).map(|(p, s)| format!("{}={}", p, s)).collect();
+            write!(out, "    %  {}", labels.join(" ")).unwrap();
+        }
+    }
+    out
+}
+
+enum Value {
+    Text(String),
+    Word(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    fn text(&self) -> String {
+        match self {
+            Value::Text(t) | Value::Word(t) => t.clone(),
+            Value::List(items) => items.iter().map(Value::text).collect::<Vec<_>>().join(" "),
+        }
+    }
+
+    fn flatten<'a>(&'a self, out: &mut Vec<&'a str>) {
+        match self {
+            Value::Text(t) | Value::Word(t) => out.push(t),
+            Value::List(items) => items.iter().for_each(|item| item.flatten(out)),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, punct: char) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Punct(c)) if c == punct => Ok(()),
+            other => Err(format!("Hugin: expected '{}', found {:?}", punct, other)),
+        }
+    }
+
+    fn keyword(&mut self, expected: &str) -> Result<(), String> {
+        match self.next() {
+            Some(Token::Word(w)) if w == expected => Ok(()),
+            other => Err(format!("Hugin: expected '{}', found {:?}", expected, other)),
+        }
+    }
+
+    fn word(&mut self) -> Result<String, String> {
+        match self.next() {
+            Some(Token::Word(w)) => Ok(w),
+            other => Err(format!("Hugin: expected a name, found {:?}", other)),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.next() {
+            Some(Token::Text(t)) => Ok(Value::Text(t)),
+            Some(Token::Word(w)) => Ok(Value::Word(w)),
+            Some(Token::Punct('(')) => {
+                let mut items = Vec::new();
+                loop {
+                    if self.tokens.get(self.pos) == Some(&Token::Punct(')')) {
+                        self.pos += 1;
+                        return Ok(Value::List(items));
+                    }
+                    items.push(self.value()?);
+                }
+            }
+            other => Err(format!("Hugin: expected a value, found {:?}", other)),
+        }
+    }
+
+    // `{ clave = valor; ... }`
+    fn attributes(&mut self) -> Result<Vec<(String, Value)>, String> {
+        self.expect('{')?;
+        let mut attributes = Vec::new();
+        loop {
+            match self.next() {
+                Some(Token::Punct('}')) => return Ok(attributes),
+                Some(Token::Word(key)) => {
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    self.expect(';')?;
+                    attributes.push((key, value));
+                }
+                other => return Err(format!("Hugin: expected an attribute, found {:?}", other)),
+            }
+        }
+    }
+
+    fn node(&mut self) -> Result<NodeSpec, String> {
+        let name = self.word()?;
+        let mut states = Vec::new();
+        let mut metadata = NodeMetadata::default();
+        let mut state_descriptions = Vec::new();
+        for (key, value) in self.attributes()? {
+            match key.as_str() {
+                "states" => {
+                    let mut flat = Vec::new();
+                    value.flatten(&mut flat);
+                    states = flat.into_iter().map(str::to_string).collect();
+                }
+                "label" => metadata.label = Some(value.text()).filter(|l| !l.is_empty()),
+                "position" => metadata.position = parse_position(&value.text()),
+                "description" => metadata.description = Some(value.text()),
+                "units" => metadata.units = Some(value.text()),
+                "role" => metadata.role = NodeRole::parse(&value.text()),
+                "state_descriptions" => {
+                    let mut flat = Vec::new();
+                    value.flatten(&mut flat);
+                    state_descriptions = flat.into_iter().map(str::to_string).collect();
+                }
+                _ => {}
+            }
+        }
+        metadata.state_descriptions = states.iter().cloned().zip(state_descriptions)
+            .filter(|(_, description)| !description.is_empty())
+            .collect();
+        Ok(NodeSpec { name, states, parents: Vec::new(), cpt: Vec::new(), metadata })
+    }
+
+    fn potential(&mut self) -> Result<(String, Vec<String>, Vec<f64>), String> {
+        self.expect('(')?;
+        let child = self.word()?;
+        let mut parents = Vec::new();
+        match self.next() {
+            Some(Token::Punct(')')) => {}
+            Some(Token::Punct('|')) => loop {
+                match self.next() {
+                    Some(Token::Punct(')')) => break,
+                    Some(Token::Word(p)) => parents.push(p),
+                    other => return Err(format!("Hugin: expected a parent, found {:?}", other)),
+                }
+            },
+            other => return Err(format!("Hugin: expected '|' or ')', found {:?}", other)),
+        }
+
+        let mut values = Vec::new();
+        for (key, value) in self.attributes()? {
+            if key == "data" {
+                let mut flat = Vec::new();
+                value.flatten(&mut flat);
+                values = flat.iter()
+                    .map(|v| v.parse().map_err(|_| format!("Hugin: '{}' is not a probability", v)))
+                    .collect::<Result<_, _>>()?;
+            }
+        }
+        Ok((child, parents, values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{build_network_internal, default_model};
+    use crate::formats::tests::assert_same_network;
+
+    #[test]
+    fn test_hugin_round_trip_is_bit_exact() {
+        let mut spec = default_model().unwrap();
+        spec.nodes[0].metadata.position = Some([120.0, 40.0]);
+        spec.nodes[0].metadata.label = Some(r#"Estado "microbiano""#.to_string());
+        let net = spec.to_hugin().unwrap();
+
+        let parsed = ModelSpec::from_hugin(&net).expect("Exported .net must parse");
+        assert_same_network(&build_network_internal().unwrap(), &parsed.build_network().unwrap());
+        assert_eq!(parsed.nodes[0].metadata, spec.nodes[0].metadata);
+        assert_eq!(parsed.to_hugin().unwrap(), net);
+
+        // Un nombre con espacios o con `%` no se podría volver a leer
+        for name in ["Nivel de agua", "pH%real"] {
+            let mut invalid = default_model().unwrap();
+            invalid.nodes[0].name = name.to_string();
+            assert!(invalid.to_hugin().unwrap_err().contains(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_hugin_two_parent_layout() {
+        // Con dos padres el primero es el más externo y el último varía más rápido
+        let spec = default_model().unwrap();
+        let net = spec.to_hugin().unwrap();
+        assert!(net.contains("potential ( ProduccionGasReal | EstadoMicrobiano CaudalReal )"));
+        assert!(net.contains("data = ((( 0.4 0.5 0.1 )    %  EstadoMicrobiano=Bueno CaudalReal=Bajo"));
+
+        let text = r#"
+            net { }
+            % comentario
+            node A { states = ("si" "no"); position = (10 20); label = "Nodo A"; HR_Color = "1"; }
+            potential (A) { data = (0.3 0.7); }
+        "#;
+        let parsed = ModelSpec::from_hugin(text).expect(".net must parse");
+        assert_eq!(parsed.nodes[0].metadata.position, Some([10.0, 20.0]));
+        assert_eq!(parsed.nodes[0].cpt[0].probabilities["no"], 0.7);
+        assert!(ModelSpec::from_hugin("continuous node X { }").is_err());
+    }
+}
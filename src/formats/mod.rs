@@ -0,0 +1,211 @@
+// Importación y exportación de formatos de intercambio de otras herramientas de
+// redes bayesianas. Todos pasan por `ModelSpec`, así que el resultado se construye
+// con el mismo `build_network` que el modelo JSON.
+
+pub mod bif;
+pub mod hugin;
+pub mod xdsl;
+pub mod xmlbif;
+mod xml;
+
+use crate::inference::factor::increment;
+use crate::model::{CptRow, ModelSpec, NodeMetadata, NodeRole, NodeSpec};
+
+/// Combinaciones de estados de los padres de `node`, con el último padre variando
+/// más rápido (orden de las tablas planas de BIF y XMLBIF).
+pub(crate) fn parent_configurations(spec: &ModelSpec, node: &NodeSpec) -> Result<Vec<Vec<String>>, String> {
+    let parent_states: Vec<&Vec<String>> = node.parents.iter()
+        .map(|p| {
+            spec.nodes.iter()
+                .find(|n| &n.name == p)
+                .map(|n| &n.states)
+                .ok_or_else(|| format!("Node '{}': unknown parent '{}'", node.name, p))
+        })
+        .collect::<Result<_, _>>()?;
+
+    let cards: Vec<usize> = parent_states.iter().map(|s| s.len()).collect();
+    let rows: usize = cards.iter().product();
+    let mut configurations = Vec::with_capacity(rows);
+    let mut assignment = vec![0; cards.len()];
+    for _ in 0..rows {
+        configurations.push(parent_states.iter().zip(&assignment).map(|(states, s)| states[*s].clone()).collect());
+        increment(&mut assignment, &cards);
+    }
+    Ok(configurations)
+}
+
+/// Fila de la CPT para `given`, en el orden de `node.states` (`None` si no está definida).
+pub(crate) fn row_values(node: &NodeSpec, given: &[String]) -> Option<Vec<f64>> {
+    node.cpt.iter()
+        .find(|row| row.given == given)
+        .map(|row| node.states.iter().map(|s| row.probabilities.get(s).copied().unwrap_or(0.0)).collect())
+}
+
+/// Fila de la CPT a partir de valores en el orden de `states`. Las filas todo cero
+/// representan combinaciones no definidas y se descartan.
+pub(crate) fn cpt_row(states: &[String], given: Vec<String>, values: &[f64]) -> Option<CptRow> {
+    if values.iter().all(|p| *p == 0.0) {
+        return None;
+    }
+    Some(CptRow { given, probabilities: states.iter().cloned().zip(values.iter().copied()).collect() })
+}
+
+/// Metadatos como pares `clave = valor`, el formato de las propiedades de BIF y XMLBIF.
+/// Las descripciones de estados van como `state_description.<estado> = texto`.
+pub(crate) fn metadata_properties(metadata: &NodeMetadata) -> Vec<String> {
+    let mut properties: Vec<String> = metadata.role.iter().map(|role| format!("role = {}", role.as_str())).collect();
+    properties.extend(
+        [("label", &metadata.label), ("description", &metadata.description), ("units", &metadata.units)]
+            .into_iter()
+            .filter_map(|(key, value)| value.as_ref().map(|v| format!("{} = {}", key, v))),
+    );
+    if let Some([x, y]) = metadata.position {
+        properties.push(format!("position = ({}, {})", x, y));
+    }
+    for (state, description) in &metadata.state_descriptions {
+        properties.push(format!("{}{} = {}", STATE_DESCRIPTION, state, description));
+    }
+    properties
+}
+
+const STATE_DESCRIPTION: &str = "state_description.";
+
+/// Interpreta una propiedad `clave = valor`; las claves desconocidas se ignoran.
+pub(crate) fn apply_property(metadata: &mut NodeMetadata, property: &str) {
+    let Some((key, value)) = property.split_once('=') else {
+        return;
+    };
+    let value = Some(value.trim().to_string());
+    match key.trim() {
+        "role" => metadata.role = value.as_deref().and_then(NodeRole::parse),
+        key if key.starts_with(STATE_DESCRIPTION) => {
+            if let Some(description) = value {
+                metadata.state_descriptions.insert(key[STATE_DESCRIPTION.len()..].to_string(), description);
+            }
+        }
+        "label" => metadata.label = value,
+        "description" => metadata.description = value,
+        "units" => metadata.units = value,
+        "position" => metadata.position = value.as_deref().and_then(parse_position),
+        _ => {}
+    }
+}
+
+/// Lee `(x, y)`, `(x y)` o `x y`; en los formatos de caja (`izq sup der inf`) toma el centro.
+pub(crate) fn parse_position(text: &str) -> Option<[f64; 2]> {
+    let numbers: Vec<f64> = text
+        .split(|c: char| c == '(' || c == ')' || c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+        .map(str::parse)
+        .collect::<Result<_, _>>()
+        .ok()?;
+    match numbers[..] {
+        [x, y] => Some([x, y]),
+        [left, top, right, bottom] => Some([(left + right) / 2.0, (top + bottom) / 2.0]),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Word(String),
+    Text(String),
+    Punct(char),
+}
+
+/// Texto entre comillas para BIF y Hugin: escapa comillas y barras como lee `tokenize`.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Comprueba que `name` sea una palabra que `tokenize` lee entera (sin espacios, signos,
+/// comillas ni comentarios), para escribirla sin comillas en BIF o Hugin. `what` describe
+/// el nombre en el mensaje de error.
+pub(crate) fn check_identifier(format: &str, line_comment: &str, what: &str, name: &str) -> Result<(), String> {
+    let invalid = name.is_empty()
+        || name.contains(|c: char| c.is_whitespace() || "{}()[],;|=\"".contains(c))
+        || name.contains(line_comment)
+        || name.starts_with("/*");
+    if invalid {
+        return Err(format!("{}: {} '{}' is not a valid identifier", format, what, name));
+    }
+    Ok(())
+}
+
+/// Tokens de los formatos de texto (BIF y Hugin): palabras, cadenas entre comillas y
+/// signos de puntuación. `line_comment` es el inicio de comentario de línea del
+/// formato (`//` o `%`); los comentarios `/* */` se aceptan siempre.
+pub(crate) fn tokenize(text: &str, line_comment: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+        } else if rest.starts_with(line_comment) {
+            rest = rest.find('\n').map_or("", |end| &rest[end + 1..]);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment.find("*/").ok_or_else(|| "Unterminated comment".to_string())?;
+            rest = &comment[end + 2..];
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            // `\"` y `\\` dentro de la cadena son una comilla y una barra (ver `escape`)
+            let mut text = String::new();
+            let mut chars = quoted.char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((i, '"')) => break i,
+                    Some((_, '\\')) if matches!(chars.clone().next(), Some((_, '"' | '\\'))) => {
+                        text.push(chars.next().unwrap().1);
+                    }
+                    Some((_, c)) => text.push(c),
+                    None => return Err("Unterminated string".to_string()),
+                }
+            };
+            tokens.push(Token::Text(text));
+            rest = &quoted[end + 1..];
+        } else if "{}()[],;|=".contains(c) {
+            tokens.push(Token::Punct(c));
+            rest = &rest[1..];
+        } else {
+            let end = rest
+                .char_indices()
+                .find(|(i, c)| c.is_whitespace() || "{}()[],;|=\"".contains(*c) || rest[*i..].starts_with(line_comment))
+                .map_or(rest.len(), |(i, _)| i);
+            tokens.push(Token::Word(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::default_model;
+    use crate::inference::tables::NetworkTables;
+    use suma_core::core::probability::bayes::BayesianNetwork;
+
+    // Compara dos redes nodo a nodo por nombre: estados, padres y cada entrada de la CPT.
+    pub(crate) fn assert_same_network(a: &BayesianNetwork, b: &BayesianNetwork) {
+        let a = NetworkTables::from_network(a).unwrap();
+        let b = NetworkTables::from_network(b).unwrap();
+        assert_eq!(a.len(), b.len());
+        for na in a.nodes() {
+            let nb = b.nodes().iter().find(|n| n.name == na.name).unwrap_or_else(|| panic!("Falta {}", na.name));
+            assert_eq!(na.states, nb.states, "{}", na.name);
+            let parents_a: Vec<&String> = na.parents.iter().map(|p| &a.nodes()[*p].name).collect();
+            let parents_b: Vec<&String> = nb.parents.iter().map(|p| &b.nodes()[*p].name).collect();
+            assert_eq!(parents_a, parents_b, "{}", na.name);
+            let bits_a: Vec<u64> = na.cpt.iter().map(|p| p.to_bits()).collect();
+            let bits_b: Vec<u64> = nb.cpt.iter().map(|p| p.to_bits()).collect();
+            assert_eq!(bits_a, bits_b, "{}", na.name);
+        }
+    }
+
+    #[test]
+    fn test_from_network_round_trip() {
+        let spec = default_model().unwrap();
+        let bn = spec.build_network().unwrap();
+        let exported = ModelSpec::from_network(&bn, &spec.name).unwrap();
+        assert_same_network(&bn, &exported.build_network().unwrap());
+    }
+}
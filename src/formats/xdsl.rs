@@ -0,0 +1,206 @@
+use std::fmt::Write;
+
+use super::xml::{escape, find_tag, strip_comments, tags, unescape};
+use super::{cpt_row, parent_configurations, parse_position, row_values};
+use crate::model::{ModelSpec, NodeMetadata, NodeRole, NodeSpec};
+
+// Formato .xdsl de GeNIe/SMILE:
+//
+// <smile version="1.0" id="Biodigestor">
+//   <nodes>
+//     <cpt id="TemperaturaReal">
+//       <state id="Baja" /> <state id="Normal" /> <state id="Alta" />
+//       <parents>EstadoMicrobiano</parents>
+//       <probabilities>0.05 0.9 0.05 0.6 0.3 0.1</probabilities>
+//     </cpt>
+//   </nodes>
+//   <extensions><genie ...><node id="TemperaturaReal"><name>Temperatura real</name>
+//     <position>84 62 156 98</position></node></genie></extensions>
+// </smile>
+//
+// Las probabilidades van con el estado del nodo más rápido y el último padre más rápido
+// entre padres. La posición de GeNIe es una caja (izq sup der inf); usamos su centro.
+// Solo se admiten nodos <cpt>; el papel del nodo va como <property id="role">. Las
+// unidades y las descripciones de estados no tienen equivalente y no se exportan.
+
+// Tamaño de la caja que se escribe alrededor de cada posición.
+const NODE_WIDTH: f64 = 72.0;
+const NODE_HEIGHT: f64 = 36.0;
+
+// Tipos de nodo de SMILE que no son tablas de probabilidad discretas.
+const UNSUPPORTED_NODES: [&str; 7] = ["deterministic", "noisymax", "noisyadder", "decision", "utility", "mau", "equation"];
+
+impl ModelSpec {
+    pub fn from_xdsl(text: &str) -> Result<ModelSpec, String> {
+        let text = strip_comments(text);
+        let smile = tags(&text, "smile").into_iter().next()
+            .ok_or_else(|| "XDSL: missing <smile>".to_string())?;
+        let nodes = tags(smile.content, "nodes").into_iter().next()
+            .ok_or_else(|| "XDSL: missing <nodes>".to_string())?
+            .content;
+        if let Some(kind) = UNSUPPORTED_NODES.iter().find(|kind| find_tag(nodes, kind).is_some()) {
+            return Err(format!("XDSL: <{}> nodes are not supported (only <cpt>)", kind));
+        }
+
+        let mut spec = ModelSpec { name: smile.attribute("id").unwrap_or_default(), description: None, nodes: Vec::new() };
+        let mut tables: Vec<Vec<f64>> = Vec::new();
+        for cpt in tags(nodes, "cpt") {
+            let name = cpt.attribute("id").ok_or_else(|| "XDSL: <cpt> without id".to_string())?;
+            let states = tags(cpt.content, "state").iter().filter_map(|s| s.attribute("id")).collect();
+            let parents = tags(cpt.content, "parents").first()
+                .map(|p| p.content.split_whitespace().map(unescape).collect())
+                .unwrap_or_default();
+            let values = tags(cpt.content, "probabilities").first()
+                .map_or("", |p| p.content)
+                .split_whitespace()
+                .map(|v| v.parse().map_err(|_| format!("XDSL: '{}' is not a probability", v)))
+                .collect::<Result<_, _>>()?;
+            let role = tags(cpt.content, "property").iter()
+                .find(|p| p.attribute("id").as_deref() == Some("role"))
+                .and_then(|p| NodeRole::parse(p.content));
+            let metadata = NodeMetadata { role, ..NodeMetadata::default() };
+            spec.nodes.push(NodeSpec { name, states, parents, cpt: Vec::new(), metadata });
+            tables.push(values);
+        }
+
+        for (index, values) in tables.into_iter().enumerate() {
+            let node = &spec.nodes[index];
+            let configurations = parent_configurations(&spec, node)?;
+            let card = node.states.len();
+            if values.len() != card * configurations.len() {
+                return Err(format!("XDSL: table of '{}' has {} values, expected {}", node.name, values.len(), card * configurations.len()));
+            }
+            let cpt = configurations.into_iter().zip(values.chunks(card))
+                .filter_map(|(given, row)| cpt_row(&node.states, given, row))
+                .collect();
+            spec.nodes[index].cpt = cpt;
+        }
+
+        // Presentación: etiqueta, comentario y posición de cada nodo
+        if let Some(genie) = tags(smile.content, "genie").first() {
+            let header = &genie.content[..find_tag(genie.content, "node").unwrap_or(genie.content.len())];
+            spec.description = tags(header, "comment").first().map(|c| unescape(c.content.trim()));
+            for element in tags(genie.content, "node") {
+                let Some(node) = element.attribute("id").and_then(|id| spec.nodes.iter_mut().find(|n| n.name == id)) else {
+                    continue;
+                };
+                let label = tags(element.content, "name").first().map(|n| unescape(n.content.trim()));
+                node.metadata.label = label.filter(|label| *label != node.name);
+                node.metadata.description = tags(element.content, "comment").first().map(|c| unescape(c.content.trim()));
+                node.metadata.position = tags(element.content, "position").first().and_then(|p| parse_position(p.content));
+            }
+        }
+        Ok(spec)
+    }
+
+    pub fn to_xdsl(&self) -> Result<String, String> {
+        let nodes = self.insertion_order()?;
+        let mut out = String::new();
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+        writeln!(out, "<smile version=\"1.0\" id=\"{}\" numsamples=\"10000\" discsamples=\"10000\">", escape(&self.name)).unwrap();
+        writeln!(out, "\t<nodes>").unwrap();
+        for node in &nodes {
+            writeln!(out, "\t\t<cpt id=\"{}\">", escape(&node.name)).unwrap();
+            for state in &node.states {
+                writeln!(out, "\t\t\t<state id=\"{}\" />", escape(state)).unwrap();
+            }
+            if !node.parents.is_empty() {
+                writeln!(out, "\t\t\t<parents>{}</parents>", escape(&node.parents.join(" "))).unwrap();
+            }
+            // Las filas no definidas se escriben como ceros (y se descartan al importar)
+            let values: Vec<String> = parent_configurations(self, node)?.iter()
+                .flat_map(|given| row_values(node, given).unwrap_or_else(|| vec![0.0; node.states.len()]))
+                .map(|p| p.to_string())
+                .collect();
+            writeln!(out, "\t\t\t<probabilities>{}</probabilities>", values.join(" ")).unwrap();
+            if let Some(role) = node.metadata.role {
+                writeln!(out, "\t\t\t<property id=\"role\">{}</property>", role.as_str()).unwrap();
+            }
+            writeln!(out, "\t\t</cpt>").unwrap();
+        }
+        writeln!(out, "\t</nodes>").unwrap();
+
+        writeln!(out, "\t<extensions>").unwrap();
+        writeln!(out, "\t\t<genie version=\"1.0\" app=\"bn_demo\" name=\"{}\">", escape(&self.name)).unwrap();
+        if let Some(description) = &self.description {
+            writeln!(out, "\t\t\t<comment>{}</comment>", escape(description)).unwrap();
+        }
+        for (i, node) in nodes.iter().enumerate() {
+            // GeNIe exige una posición: los nodos sin ella se colocan en una grilla
+            let [x, y] = node.metadata.position.unwrap_or([100.0 + 150.0 * (i % 4) as f64, 60.0 + 100.0 * (i / 4) as f64]);
+            let left = (x - NODE_WIDTH / 2.0).round();
+            let top = (y - NODE_HEIGHT / 2.0).round();
+            writeln!(out, "\t\t\t<node id=\"{}\">", escape(&node.name)).unwrap();
+            writeln!(out, "\t\t\t\t<name>{}</name>", escape(node.metadata.label.as_deref().unwrap_or(&node.name))).unwrap();
+            writeln!(out, "\t\t\t\t<interior color=\"e5f6f7\" />").unwrap();
+            writeln!(out, "\t\t\t\t<outline color=\"000080\" />").unwrap();
+            writeln!(out, "\t\t\t\t<font color=\"000000\" name=\"Arial\" size=\"8\" />").unwrap();
+            writeln!(out, "\t\t\t\t<position>{} {} {} {}</position>", left, top, left + NODE_WIDTH, top + NODE_HEIGHT).unwrap();
+            if let Some(description) = &node.metadata.description {
+                writeln!(out, "\t\t\t\t<comment>{}</comment>", escape(description)).unwrap();
+            }
+            writeln!(out, "\t\t\t</node>").unwrap();
+        }
+        writeln!(out, "\t\t</genie>").unwrap();
+        writeln!(out, "\t</extensions>").unwrap();
+        writeln!(out, "</smile>").unwrap();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{build_network_internal, default_model};
+    use crate::formats::tests::assert_same_network;
+
+    #[test]
+    fn test_xdsl_round_trip_is_bit_exact() {
+        let mut spec = default_model().unwrap();
+        for (i, node) in spec.nodes.iter_mut().enumerate() {
+            node.metadata.position = Some([100.0 + 50.0 * i as f64, 200.0]);
+            node.metadata.units = None;
+            node.metadata.state_descriptions.clear();
+        }
+        let xdsl = spec.to_xdsl().unwrap();
+
+        let parsed = ModelSpec::from_xdsl(&xdsl).expect("Exported .xdsl must parse");
+        assert_same_network(&build_network_internal().unwrap(), &parsed.build_network().unwrap());
+        for (original, imported) in spec.nodes.iter().zip(&parsed.nodes) {
+            assert_eq!(original.metadata, imported.metadata, "{}", original.name);
+        }
+        assert_eq!(parsed.description, spec.description);
+        assert_eq!(parsed.to_xdsl().unwrap(), xdsl);
+    }
+
+    #[test]
+    fn test_xdsl_genie_file() {
+        let xdsl = r#"<?xml version="1.0" encoding="ISO-8859-1"?>
+            <smile version="1.0" id="Network1" numsamples="1000">
+              <nodes>
+                <cpt id="Lluvia"><state id="Si" /><state id="No" /><probabilities>0.2 0.8</probabilities></cpt>
+                <cpt id="Pasto">
+                  <state id="Mojado" /><state id="Seco" />
+                  <parents>Lluvia</parents>
+                  <probabilities>0.9 0.1 0.25 0.75</probabilities>
+                </cpt>
+              </nodes>
+              <extensions>
+                <genie version="1.0" app="GeNIe 2.0" name="Network1">
+                  <node id="Lluvia"><name>Lluvia</name><position>40 30 112 66</position></node>
+                  <node id="Pasto"><name>Pasto mojado</name><position>40 130 112 166</position></node>
+                </genie>
+              </extensions>
+            </smile>"#;
+        let spec = ModelSpec::from_xdsl(xdsl).expect(".xdsl must parse");
+        assert_eq!(spec.name, "Network1");
+        assert_eq!(spec.nodes[0].metadata.label, None);
+        assert_eq!(spec.nodes[1].metadata.label.as_deref(), Some("Pasto mojado"));
+        assert_eq!(spec.nodes[1].metadata.position, Some([76.0, 148.0]));
+        assert_eq!(spec.nodes[1].cpt[1].probabilities["Seco"], 0.75);
+        assert!(spec.build_network().is_ok());
+
+        let with_decision = xdsl.replace("<cpt id=\"Lluvia\">", "<decision id=\"D\"><state id=\"a\" /></decision><cpt id=\"Lluvia\">");
+        assert!(ModelSpec::from_xdsl(&with_decision).is_err());
+    }
+}
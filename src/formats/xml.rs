@@ -0,0 +1,95 @@
+// Lectura mínima de XML para XMLBIF y XDSL. Ambos formatos son lo bastante simples
+// como para no necesitar un parser completo: los elementos que nos interesan no se
+// anidan dentro de sí mismos. Las etiquetas se buscan sin distinguir mayúsculas.
+
+/// Un elemento `<tag atributos>contenido</tag>` (o `<tag atributos/>`, con contenido vacío).
+pub(crate) struct XmlElement<'a> {
+    pub attributes: &'a str,
+    pub content: &'a str,
+}
+
+impl XmlElement<'_> {
+    /// Valor (sin escapes) del atributo `name="..."`.
+    pub fn attribute(&self, name: &str) -> Option<String> {
+        let mut rest = self.attributes;
+        while let Some(eq) = rest.find('=') {
+            let key = rest[..eq].trim();
+            let value = rest[eq + 1..].trim_start();
+            let quote = value.chars().next()?;
+            let end = value[1..].find(quote)? + 1;
+            if key.eq_ignore_ascii_case(name) {
+                return Some(unescape(&value[1..end]));
+            }
+            rest = &value[end + 1..];
+        }
+        None
+    }
+}
+
+pub(crate) fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<!--") {
+        out.push_str(&rest[..start]);
+        rest = rest[start..].find("-->").map_or("", |end| &rest[start + end + 3..]);
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Posición de la primera etiqueta de apertura `<TAG>` o `<TAG ...>`.
+pub(crate) fn find_tag(text: &str, tag: &str) -> Option<usize> {
+    let upper = text.to_ascii_uppercase();
+    let open = format!("<{}", tag.to_ascii_uppercase());
+    let mut from = 0;
+    while let Some(found) = upper[from..].find(&open) {
+        let start = from + found;
+        match upper[start + open.len()..].chars().next() {
+            Some(c) if c == '>' || c == '/' || c.is_whitespace() => return Some(start),
+            _ => from = start + open.len(),
+        }
+    }
+    None
+}
+
+/// Elementos `<tag>` de primer nivel dentro de `text`, en orden.
+pub(crate) fn tags<'a>(text: &'a str, tag: &str) -> Vec<XmlElement<'a>> {
+    let upper = text.to_ascii_uppercase();
+    let close = format!("</{}>", tag.to_ascii_uppercase());
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = find_tag(&text[from..], tag).map(|s| from + s) {
+        let Some(open_end) = text[start..].find('>').map(|e| start + e + 1) else {
+            break;
+        };
+        let name_end = start + 1 + tag.len();
+        if text[..open_end].ends_with("/>") {
+            found.push(XmlElement { attributes: &text[name_end..open_end - 2], content: "" });
+            from = open_end;
+            continue;
+        }
+        let Some(end) = upper[open_end..].find(&close).map(|e| open_end + e) else {
+            break;
+        };
+        found.push(XmlElement { attributes: &text[name_end..open_end - 1], content: &text[open_end..end] });
+        from = end + close.len();
+    }
+    found
+}
+
+/// Contenido de cada elemento `<tag>` de primer nivel.
+pub(crate) fn elements<'a>(text: &'a str, tag: &str) -> Vec<&'a str> {
+    tags(text, tag).into_iter().map(|e| e.content).collect()
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+pub(crate) fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
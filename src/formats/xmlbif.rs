@@ -0,0 +1,154 @@
+use std::fmt::Write;
+
+use super::{apply_property, cpt_row, metadata_properties, parent_configurations, row_values};
+use super::xml::{elements, escape, find_tag, strip_comments, unescape};
+use crate::model::{ModelSpec, NodeMetadata, NodeSpec};
+
+// XMLBIF 0.3: <VARIABLE> con <NAME>, <OUTCOME> y <PROPERTY>; <DEFINITION> con <FOR>,
+// <GIVEN> y una <TABLE> plana donde el estado del nodo varía más rápido y, entre
+// padres, el último <GIVEN> es el más rápido. Se aceptan también los nombres de la
+// versión 0.1 (<VALUE>, <PROBABILITY>) y la propiedad `position = (x, y)` de JavaBayes.
+
+impl ModelSpec {
+    pub fn from_xmlbif(text: &str) -> Result<ModelSpec, String> {
+        let text = strip_comments(text);
+        let network = elements(&text, "NETWORK").into_iter().next()
+            .ok_or_else(|| "XMLBIF: missing <NETWORK>".to_string())?;
+
+        // El nombre de la red es el <NAME> anterior a la primera variable
+        let header = &network[..find_tag(network, "VARIABLE").unwrap_or(network.len())];
+        let name = elements(header, "NAME").first().map(|n| unescape(n.trim())).unwrap_or_default();
+        let mut network_metadata = NodeMetadata::default();
+        for property in elements(header, "PROPERTY") {
+            apply_property(&mut network_metadata, &unescape(property));
+        }
+
+        let mut spec = ModelSpec { name, description: network_metadata.description, nodes: Vec::new() };
+        for variable in elements(network, "VARIABLE") {
+            let name = elements(variable, "NAME").first().map(|n| unescape(n.trim()))
+                .ok_or_else(|| "XMLBIF: <VARIABLE> without <NAME>".to_string())?;
+            let mut states: Vec<String> = elements(variable, "OUTCOME").iter().map(|s| unescape(s.trim())).collect();
+            if states.is_empty() {
+                states = elements(variable, "VALUE").iter().map(|s| unescape(s.trim())).collect();
+            }
+            let mut metadata = NodeMetadata::default();
+            for property in elements(variable, "PROPERTY") {
+                apply_property(&mut metadata, &unescape(property));
+            }
+            spec.nodes.push(NodeSpec { name, states, parents: Vec::new(), cpt: Vec::new(), metadata });
+        }
+
+        let mut definitions = elements(network, "DEFINITION");
+        if definitions.is_empty() {
+            definitions = elements(network, "PROBABILITY");
+        }
+        for definition in definitions {
+            let child = elements(definition, "FOR").first().map(|n| unescape(n.trim()))
+                .ok_or_else(|| "XMLBIF: <DEFINITION> without <FOR>".to_string())?;
+            let index = spec.nodes.iter().position(|n| n.name == child)
+                .ok_or_else(|| format!("XMLBIF: definition for undeclared variable '{}'", child))?;
+            spec.nodes[index].parents = elements(definition, "GIVEN").iter().map(|g| unescape(g.trim())).collect();
+
+            let table = elements(definition, "TABLE").first().copied().unwrap_or("");
+            let values: Vec<f64> = table.split_whitespace()
+                .map(|v| v.parse().map_err(|_| format!("XMLBIF: '{}' is not a probability", v)))
+                .collect::<Result<_, _>>()?;
+
+            let node = &spec.nodes[index];
+            let configurations = parent_configurations(&spec, node)?;
+            let card = node.states.len();
+            if values.len() != card * configurations.len() {
+                return Err(format!("XMLBIF: table of '{}' has {} values, expected {}", child, values.len(), card * configurations.len()));
+            }
+            let cpt = configurations.into_iter().zip(values.chunks(card))
+                .filter_map(|(given, row)| cpt_row(&node.states, given, row))
+                .collect();
+            spec.nodes[index].cpt = cpt;
+        }
+        Ok(spec)
+    }
+
+    pub fn to_xmlbif(&self) -> Result<String, String> {
+        let mut out = String::new();
+        writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+        writeln!(out, "<BIF VERSION=\"0.3\">").unwrap();
+        writeln!(out, "<NETWORK>").unwrap();
+        writeln!(out, "<NAME>{}</NAME>", escape(&self.name)).unwrap();
+        if let Some(description) = &self.description {
+            writeln!(out, "<PROPERTY>description = {}</PROPERTY>", escape(description)).unwrap();
+        }
+
+        for node in &self.nodes {
+            writeln!(out, "<VARIABLE TYPE=\"nature\">").unwrap();
+            writeln!(out, "  <NAME>{}</NAME>", escape(&node.name)).unwrap();
+            for state in &node.states {
+                writeln!(out, "  <OUTCOME>{}</OUTCOME>", escape(state)).unwrap();
+            }
+            for property in metadata_properties(&node.metadata) {
+                writeln!(out, "  <PROPERTY>{}</PROPERTY>", escape(&property)).unwrap();
+            }
+            writeln!(out, "</VARIABLE>").unwrap();
+        }
+
+        for node in &self.nodes {
+            writeln!(out, "<DEFINITION>").unwrap();
+            writeln!(out, "  <FOR>{}</FOR>", escape(&node.name)).unwrap();
+            for parent in &node.parents {
+                writeln!(out, "  <GIVEN>{}</GIVEN>", escape(parent)).unwrap();
+            }
+            // Las filas no definidas se escriben como ceros (y se descartan al importar)
+            let values: Vec<String> = parent_configurations(self, node)?.iter()
+                .flat_map(|given| row_values(node, given).unwrap_or_else(|| vec![0.0; node.states.len()]))
+                .map(|p| p.to_string())
+                .collect();
+            writeln!(out, "  <TABLE>{}</TABLE>", values.join(" ")).unwrap();
+            writeln!(out, "</DEFINITION>").unwrap();
+        }
+
+        writeln!(out, "</NETWORK>").unwrap();
+        writeln!(out, "</BIF>").unwrap();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::{build_network_internal, default_model};
+    use crate::formats::tests::assert_same_network;
+
+    #[test]
+    fn test_xmlbif_round_trip_is_bit_exact() {
+        let spec = default_model().unwrap();
+        let xml = spec.to_xmlbif().unwrap();
+
+        let parsed = ModelSpec::from_xmlbif(&xml).expect("Exported XMLBIF must parse");
+        assert_same_network(&build_network_internal().unwrap(), &parsed.build_network().unwrap());
+        assert_eq!(parsed.name, spec.name);
+        assert_eq!(parsed.metadata()["EstadoMicrobiano"].label.as_deref(), Some("Estado microbiano"));
+        assert_eq!(parsed.to_xmlbif().unwrap(), xml);
+    }
+
+    #[test]
+    fn test_xmlbif_javabayes_example() {
+        // Fragmento del clásico "dog-problem" de JavaBayes
+        let xml = r#"<?xml version="1.0"?>
+            <!-- comentario -->
+            <BIF VERSION="0.3"><NETWORK>
+            <NAME>Dog-Problem</NAME>
+            <VARIABLE TYPE="nature"><NAME>family-out</NAME><OUTCOME>true</OUTCOME><OUTCOME>false</OUTCOME>
+              <PROPERTY>position = (112, 69)</PROPERTY></VARIABLE>
+            <VARIABLE TYPE="nature"><NAME>light-on</NAME><OUTCOME>true</OUTCOME><OUTCOME>false</OUTCOME></VARIABLE>
+            <DEFINITION><FOR>light-on</FOR><GIVEN>family-out</GIVEN><TABLE>0.6 0.4 0.05 0.95 </TABLE></DEFINITION>
+            <DEFINITION><FOR>family-out</FOR><TABLE>0.15 0.85 </TABLE></DEFINITION>
+            </NETWORK></BIF>"#;
+        let spec = ModelSpec::from_xmlbif(xml).expect("XMLBIF must parse");
+        assert_eq!(spec.name, "Dog-Problem");
+        let light = &spec.nodes[1];
+        assert_eq!(light.parents, vec!["family-out"]);
+        assert_eq!(light.cpt[1].given, vec!["false"]);
+        assert_eq!(light.cpt[1].probabilities["true"], 0.05);
+        assert_eq!(spec.nodes[0].metadata.position, Some([112.0, 69.0]));
+        assert!(spec.build_network().is_ok());
+    }
+}
@@ -0,0 +1,207 @@
+// Factores discretos sobre índices de nodo (no ids de suma_core).
+//
+// Las variables de un factor se guardan ordenadas de forma ascendente y la
+// tabla de valores es row-major: la última variable es la que varía más rápido.
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Factor {
+    pub vars: Vec<usize>,
+    pub cards: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl Factor {
+    /// Factor constante 1 sin variables (neutro del producto).
+    pub fn unit() -> Self {
+        Factor { vars: vec![], cards: vec![], values: vec![1.0] }
+    }
+
+    /// Construye un factor evaluando `f` sobre cada asignación de `vars`.
+    /// `vars` debe venir ordenado; la asignación que recibe `f` sigue ese orden.
+    pub fn from_fn<F>(vars: Vec<usize>, cards: Vec<usize>, f: F) -> Self
+    where
+        F: Fn(&[usize]) -> f64,
+    {
+        debug_assert!(vars.windows(2).all(|w| w[0] < w[1]), "Factor vars must be sorted");
+        let len: usize = cards.iter().product();
+        let mut values = Vec::with_capacity(len);
+        let mut assignment = vec![0; vars.len()];
+        for _ in 0..len {
+            values.push(f(&assignment));
+            increment(&mut assignment, &cards);
+        }
+        Factor { vars, cards, values }
+    }
+
+    pub fn position(&self, var: usize) -> Option<usize> {
+        self.vars.binary_search(&var).ok()
+    }
+
+    pub fn card_of(&self, var: usize) -> Option<usize> {
+        self.position(var).map(|p| self.cards[p])
+    }
+
+    fn strides(&self) -> Vec<usize> {
+        let mut strides = vec![1; self.vars.len()];
+        for k in (0..self.vars.len().saturating_sub(1)).rev() {
+            strides[k] = strides[k + 1] * self.cards[k + 1];
+        }
+        strides
+    }
+
+    pub fn total(&self) -> f64 {
+        self.values.iter().sum()
+    }
+
+    pub fn product(&self, other: &Factor) -> Factor {
+        let mut vars: Vec<usize> = self.vars.iter().chain(other.vars.iter()).copied().collect();
+        vars.sort_unstable();
+        vars.dedup();
+
+        let cards: Vec<usize> = vars
+            .iter()
+            .map(|v| self.card_of(*v).or_else(|| other.card_of(*v)).unwrap_or(1))
+            .collect();
+
+        let a_strides = self.strides_in(&vars);
+        let b_strides = other.strides_in(&vars);
+
+        let len: usize = cards.iter().product();
+        let mut values = Vec::with_capacity(len);
+        let mut assignment = vec![0; vars.len()];
+        let (mut ia, mut ib) = (0usize, 0usize);
+
+        for _ in 0..len {
+            values.push(self.values[ia] * other.values[ib]);
+            for k in (0..vars.len()).rev() {
+                assignment[k] += 1;
+                ia += a_strides[k];
+                ib += b_strides[k];
+                if assignment[k] < cards[k] {
+                    break;
+                }
+                ia -= a_strides[k] * cards[k];
+                ib -= b_strides[k] * cards[k];
+                assignment[k] = 0;
+            }
+        }
+
+        Factor { vars, cards, values }
+    }
+
+    // Stride de cada variable de `vars` dentro de este factor (0 si no aparece).
+    fn strides_in(&self, vars: &[usize]) -> Vec<usize> {
+        let own = self.strides();
+        vars.iter()
+            .map(|v| self.position(*v).map_or(0, |p| own[p]))
+            .collect()
+    }
+
+    /// Suma la variable `var` (si no está en el factor, lo devuelve intacto).
+    pub fn sum_out(&self, var: usize) -> Factor {
+        self.eliminate(var, |acc, x| acc + x, 0.0)
+    }
+
+    fn eliminate<F>(&self, var: usize, fold: F, init: f64) -> Factor
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let Some(pos) = self.position(var) else {
+            return self.clone();
+        };
+
+        let outer: usize = self.cards[..pos].iter().product();
+        let card = self.cards[pos];
+        let inner: usize = self.cards[pos + 1..].iter().product();
+
+        let mut values = vec![init; outer * inner];
+        for o in 0..outer {
+            for s in 0..card {
+                let base = (o * card + s) * inner;
+                for i in 0..inner {
+                    let slot = &mut values[o * inner + i];
+                    *slot = fold(*slot, self.values[base + i]);
+                }
+            }
+        }
+
+        let mut vars = self.vars.clone();
+        let mut cards = self.cards.clone();
+        vars.remove(pos);
+        cards.remove(pos);
+
+        Factor { vars, cards, values }
+    }
+
+    /// Suma todas las variables que no estén en `keep`.
+    pub fn project(&self, keep: &[usize]) -> Factor {
+        let mut factor = self.clone();
+        for var in self.vars.iter().filter(|v| !keep.contains(v)) {
+            factor = factor.sum_out(*var);
+        }
+        factor
+    }
+
+    /// Anula las entradas incompatibles con `var = state`, conservando el alcance.
+    pub fn observe(&mut self, var: usize, state: usize) {
+        let Some(pos) = self.position(var) else {
+            return;
+        };
+        let stride = self.strides()[pos];
+        let card = self.cards[pos];
+        for (index, value) in self.values.iter_mut().enumerate() {
+            if (index / stride) % card != state {
+                *value = 0.0;
+            }
+        }
+    }
+
+    pub fn normalized(mut self) -> Factor {
+        let total = self.total();
+        if total > 0.0 {
+            for value in &mut self.values {
+                *value /= total;
+            }
+        }
+        self
+    }
+}
+
+// Avanza una asignación row-major (última variable más rápida).
+pub(crate) fn increment(assignment: &mut [usize], cards: &[usize]) {
+    for k in (0..assignment.len()).rev() {
+        assignment[k] += 1;
+        if assignment[k] < cards[k] {
+            return;
+        }
+        assignment[k] = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_product_and_sum_out() {
+        // P(A) y P(B|A) con A, B binarias
+        let pa = Factor { vars: vec![0], cards: vec![2], values: vec![0.6, 0.4] };
+        let pb_a = Factor { vars: vec![0, 1], cards: vec![2, 2], values: vec![0.8, 0.2, 0.3, 0.7] };
+
+        let joint = pa.product(&pb_a);
+        assert_eq!(joint.vars, vec![0, 1]);
+        assert_eq!(joint.values.len(), 4);
+
+        let pb = joint.sum_out(0);
+        assert_eq!(pb.vars, vec![1]);
+        assert!((pb.values[0] - 0.6).abs() < 1e-12, "P(B=0) = {}", pb.values[0]);
+        assert!((pb.values[1] - 0.4).abs() < 1e-12, "P(B=1) = {}", pb.values[1]);
+    }
+
+    #[test]
+    fn test_observe() {
+        let mut f = Factor { vars: vec![0, 1], cards: vec![2, 3], values: vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0] };
+        f.observe(1, 2);
+        assert_eq!(f.values, vec![0.0, 0.0, 3.0, 0.0, 0.0, 6.0]);
+    }
+}
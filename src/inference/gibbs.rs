@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use suma_core::core::probability::bayes::BN_base::State;
+
+use super::sampling::{now_ms, SampleRng, SampledPosterior};
+use super::tables::NetworkTables;
+use super::InferenceOptions;
+
+// Muestreo de Gibbs: cada variable no observada se remuestrea condicionada a su
+// manto de Markov. A diferencia de la ponderación de verosimilitud, la evidencia
+// poco probable no degenera los pesos, porque todas las muestras valen lo mismo.
+
+// Intentos de muestreo hacia adelante para encontrar un estado inicial con probabilidad positiva.
+const MAX_INIT_ATTEMPTS: usize = 10_000;
+
+/// Posterior de `query` por Gibbs con `options.chains` cadenas, `burn_in` barridos
+/// descartados y un barrido de cada `thinning` conservado. `samples` es el total de
+/// muestras conservadas, repartido entre las cadenas. El presupuesto de tiempo se
+/// respeta también durante el burn-in y, agotado, no se empiezan más cadenas. `None`
+/// si no se conservó ninguna muestra (sin estado inicial compatible con la evidencia
+/// o sin tiempo para terminar el burn-in). Las opciones se validan con `check()`.
+pub(crate) fn gibbs_posterior(
+    tables: &NetworkTables,
+    evidence: &HashMap<usize, State>,
+    query: usize,
+    options: &InferenceOptions,
+) -> Result<Option<SampledPosterior>, String> {
+    let query_index = tables
+        .index_of(query)
+        .ok_or_else(|| format!("Query node {} not found", query))?;
+
+    let Some(observed) = tables.evidence_vector(evidence) else {
+        return Ok(None);
+    };
+
+    let chains = options.chains;
+    let thinning = options.thinning;
+    let deadline = options.max_time_ms.map(|budget| now_ms() + budget);
+    let out_of_time = || deadline.is_some_and(|deadline| now_ms() >= deadline);
+    let children = children_of(tables);
+    let hidden: Vec<usize> = (0..tables.len()).filter(|v| observed[*v].is_none()).collect();
+
+    let mut base_rng = SampleRng::from_options(options);
+    let card = tables.card(query_index);
+    // counts[c][s]: visitas de la cadena c al estado s del nodo consultado
+    let mut counts: Vec<Vec<usize>> = Vec::with_capacity(chains);
+    let mut kept: Vec<usize> = Vec::with_capacity(chains);
+
+    for chain in 0..chains {
+        // Las primeras `samples % chains` cadenas conservan una muestra más
+        let per_chain = options.samples / chains + usize::from(chain < options.samples % chains);
+        let mut rng = SampleRng::new(base_rng.next_u64());
+        if per_chain == 0 || (chain > 0 && out_of_time()) {
+            break;
+        }
+        let Some(mut state) = initial_state(tables, &observed, &mut rng) else {
+            return Ok(None);
+        };
+
+        let mut chain_counts = vec![0usize; card];
+        let mut chain_kept = 0;
+        let mut sweep = 0usize;
+        while chain_kept < per_chain {
+            // En el burn-in también; después, cada cadena aporta al menos una muestra
+            let burning_in = sweep < options.burn_in;
+            if sweep.is_multiple_of(256) && (burning_in || chain_kept > 0) && out_of_time() {
+                break;
+            }
+
+            for &var in &hidden {
+                let conditional = markov_blanket_conditional(tables, &children, &mut state, var);
+                state[var] = rng.categorical(&conditional);
+            }
+
+            sweep += 1;
+            if sweep > options.burn_in && (sweep - options.burn_in).is_multiple_of(thinning) {
+                chain_counts[state[query_index]] += 1;
+                chain_kept += 1;
+            }
+        }
+
+        if chain_kept > 0 {
+            counts.push(chain_counts);
+            kept.push(chain_kept);
+        }
+    }
+
+    let total: usize = kept.iter().sum();
+    if total == 0 {
+        return Ok(None);
+    }
+
+    let probabilities: Vec<f64> = (0..card)
+        .map(|s| counts.iter().map(|c| c[s]).sum::<usize>() as f64 / total as f64)
+        .collect();
+
+    let (r_hat, effective_sample_size) = gelman_rubin(&counts, &kept, card);
+    let std_errors: Vec<f64> = probabilities
+        .iter()
+        .map(|p| (p * (1.0 - p) / effective_sample_size.max(1.0)).sqrt())
+        .collect();
+
+    Ok(Some(SampledPosterior {
+        distribution: tables.to_distribution(query_index, &probabilities),
+        std_errors: tables.to_distribution(query_index, &std_errors),
+        effective_sample_size,
+        samples: total,
+        converged: None,
+        r_hat,
+        evidence: None,
+    }))
+}
+
+pub(crate) fn children_of(tables: &NetworkTables) -> Vec<Vec<usize>> {
+    let mut children = vec![Vec::new(); tables.len()];
+    for (child, node) in tables.nodes().iter().enumerate() {
+        for &p in &node.parents {
+            children[p].push(child);
+        }
+    }
+    children
+}
+
+/// P(var = s | manto de Markov) sin normalizar, para cada estado s.
+pub(crate) fn markov_blanket_conditional(
+    tables: &NetworkTables,
+    children: &[Vec<usize>],
+    state: &mut [usize],
+    var: usize,
+) -> Vec<f64> {
+    let current = state[var];
+    let conditional = (0..tables.card(var))
+        .map(|s| {
+            state[var] = s;
+            let own = tables.cpt_row(var, tables.parent_row(var, state))[s];
+            children[var].iter().fold(own, |acc, &c| {
+                acc * tables.cpt_row(c, tables.parent_row(c, state))[state[c]]
+            })
+        })
+        .collect::<Vec<f64>>();
+    state[var] = current;
+
+    // Si todo es cero (estado inicial degenerado), no mover la variable
+    if conditional.iter().all(|p| *p <= 0.0) {
+        let mut stay = vec![0.0; conditional.len()];
+        stay[current] = 1.0;
+        return stay;
+    }
+    conditional
+}
+
+// Muestreo hacia adelante con la evidencia fijada hasta dar con un estado de probabilidad positiva.
+pub(crate) fn initial_state(tables: &NetworkTables, observed: &[Option<usize>], rng: &mut SampleRng) -> Option<Vec<usize>> {
+    let order = tables.topological_order();
+    let mut state = vec![0usize; tables.len()];
+    for _ in 0..MAX_INIT_ATTEMPTS {
+        let mut weight = 1.0;
+        for &node in &order {
+            let probs = tables.cpt_row(node, tables.parent_row(node, &state));
+            match observed[node] {
+                Some(s) => {
+                    state[node] = s;
+                    weight *= probs[s];
+                }
+                None => state[node] = rng.categorical(probs),
+            }
+        }
+        if weight > 0.0 {
+            return Some(state);
+        }
+    }
+    None
+}
+
+/// R-hat de Gelman-Rubin (máximo sobre los indicadores de cada estado) y tamaño
+/// efectivo de muestra m·n·W/var⁺ (el mínimo sobre estados). R-hat es `None` con una sola cadena.
+fn gelman_rubin(counts: &[Vec<usize>], kept: &[usize], card: usize) -> (Option<f64>, f64) {
+    let m = counts.len();
+    let total: usize = kept.iter().sum();
+    let n = kept.iter().copied().min().unwrap_or(0);
+    if m < 2 || n < 2 {
+        return (None, total as f64);
+    }
+
+    let nf = n as f64;
+    let mut worst_r_hat: f64 = 1.0;
+    let mut min_ess = total as f64;
+    for s in 0..card {
+        // Media y varianza muestral del indicador 1[x = s] en cada cadena
+        let means: Vec<f64> = counts.iter().zip(kept).map(|(c, k)| c[s] as f64 / *k as f64).collect();
+        let within: f64 = means
+            .iter()
+            .zip(kept)
+            .map(|(p, k)| p * (1.0 - p) * (*k as f64) / (*k as f64 - 1.0))
+            .sum::<f64>()
+            / m as f64;
+        let grand = means.iter().sum::<f64>() / m as f64;
+        let between = nf / (m as f64 - 1.0) * means.iter().map(|p| (p - grand).powi(2)).sum::<f64>();
+        let pooled = (nf - 1.0) / nf * within + between / nf;
+
+        if within > 0.0 {
+            worst_r_hat = worst_r_hat.max((pooled / within).sqrt());
+            min_ess = min_ess.min(total as f64 * within / pooled);
+        } else if between > 0.0 {
+            worst_r_hat = f64::INFINITY;
+        }
+    }
+
+    (Some(worst_r_hat), min_ess)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::build_network_internal;
+    use crate::inference::variable_elimination::variable_elimination;
+
+    fn all_sensors_abnormal(bn: &suma_core::core::probability::bayes::BayesianNetwork) -> HashMap<usize, State> {
+        [("T_sensor", "alta"), ("pH_sensor", "alcalino"), ("Flow_sensor", "bajo"), ("Gas_sensor", "alto"), ("Presion_sensor", "alta")]
+            .into_iter()
+            .map(|(node, value)| (bn.get_id_from_name(node).unwrap(), State::Value(value.to_string())))
+            .collect()
+    }
+
+    #[test]
+    fn test_gibbs_matches_exact_with_unlikely_evidence() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let evidence = all_sensors_abnormal(&bn);
+
+        let options = InferenceOptions { samples: 40_000, seed: Some(5), burn_in: 500, chains: 4, ..InferenceOptions::default() };
+        for target_name in ["EstadoMicrobiano", "EstadoOperativo"] {
+            let target = bn.get_id_from_name(target_name).unwrap();
+            let gibbs = gibbs_posterior(&tables, &evidence, target, &options).unwrap().expect("Gibbs sin estado inicial");
+            let exact = variable_elimination(&tables, &evidence, target).unwrap();
+
+            for (state, p) in &exact {
+                let q = gibbs.distribution[state];
+                assert!((p - q).abs() < 0.03, "{} = {:?}: exacto {} vs Gibbs {}", target_name, state, p, q);
+            }
+            let r_hat = gibbs.r_hat.expect("Con 4 cadenas debe haber R-hat");
+            assert!(r_hat < 1.1, "R-hat demasiado alto: {}", r_hat);
+            assert_eq!(gibbs.samples, 40_000);
+        }
+    }
+
+    #[test]
+    fn test_gibbs_is_reproducible_and_thins() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let evidence = all_sensors_abnormal(&bn);
+        let target = bn.get_id_from_name("EstadoOperativo").unwrap();
+
+        let options = InferenceOptions { samples: 3_000, seed: Some(9), thinning: 3, chains: 2, ..InferenceOptions::default() };
+        let first = gibbs_posterior(&tables, &evidence, target, &options).unwrap().unwrap();
+        let second = gibbs_posterior(&tables, &evidence, target, &options).unwrap().unwrap();
+        assert_eq!(first.distribution, second.distribution);
+        assert_eq!(first.samples, 3_000);
+
+        // Una sola cadena no permite calcular R-hat
+        let single = InferenceOptions { chains: 1, ..options };
+        assert!(gibbs_posterior(&tables, &evidence, target, &single).unwrap().unwrap().r_hat.is_none());
+
+        // Nunca más muestras que `samples`, aunque no se repartan a partes iguales
+        for samples in [1_001, 3] {
+            let uneven = InferenceOptions { samples, chains: 4, burn_in: 10, ..options.clone() };
+            assert_eq!(gibbs_posterior(&tables, &evidence, target, &uneven).unwrap().unwrap().samples, samples);
+        }
+        assert!(InferenceOptions { chains: 0, ..options.clone() }.check().is_err());
+        assert!(InferenceOptions { thinning: 0, ..options }.check().is_err());
+    }
+
+    #[test]
+    fn test_time_budget_applies_to_burn_in() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let target = bn.get_id_from_name("EstadoOperativo").unwrap();
+
+        // Sin tiempo para terminar el burn-in no queda ninguna muestra (y no tarda)
+        let options = InferenceOptions { samples: 100, seed: Some(2), burn_in: 100_000_000, max_time_ms: Some(1e-9), ..InferenceOptions::default() };
+        assert!(gibbs_posterior(&tables, &all_sensors_abnormal(&bn), target, &options).unwrap().is_none());
+    }
+}
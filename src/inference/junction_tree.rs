@@ -0,0 +1,320 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap};
+use std::rc::Rc;
+use suma_core::core::probability::bayes::BN_base::State;
+
+use super::factor::Factor;
+use super::tables::NetworkTables;
+
+/// Árbol de cliques compilado una sola vez a partir de la red.
+///
+/// Compilación: moralización, triangulación (eliminación min-fill), cliques
+/// maximales y árbol de expansión máximo por tamaño de separador. Cada CPT se
+/// asigna a un clique que contiene a su familia.
+pub(crate) struct JunctionTree {
+    cliques: Vec<Vec<usize>>,
+    potentials: Vec<Factor>,
+    neighbors: Vec<Vec<usize>>,
+    /// Clique más pequeño que contiene a cada variable.
+    home: Vec<usize>,
+    /// Última calibración, reutilizada mientras la evidencia no cambie.
+    last: RefCell<Option<CachedCalibration>>,
+}
+
+type CachedCalibration = (Vec<Option<usize>>, Rc<Calibration>);
+
+/// Creencias de los cliques tras una pasada de calibración.
+pub(crate) struct Calibration {
+    beliefs: Vec<Factor>,
+    evidence_probability: f64,
+}
+
+impl JunctionTree {
+    pub fn compile(tables: &NetworkTables) -> Self {
+        let n = tables.len();
+
+        // 1. Moralización: unir padres entre sí y quitar dirección
+        let mut adjacency: Vec<BTreeSet<usize>> = vec![BTreeSet::new(); n];
+        for (child, node) in tables.nodes().iter().enumerate() {
+            for (i, &a) in node.parents.iter().enumerate() {
+                adjacency[a].insert(child);
+                adjacency[child].insert(a);
+                for &b in &node.parents[i + 1..] {
+                    adjacency[a].insert(b);
+                    adjacency[b].insert(a);
+                }
+            }
+        }
+
+        // 2. Triangulación por eliminación min-fill; cada paso produce un clique candidato
+        let mut candidates: Vec<Vec<usize>> = Vec::new();
+        let mut remaining: BTreeSet<usize> = (0..n).collect();
+        while let Some(var) = min_fill_variable(&adjacency, &remaining, tables) {
+            let neighbors: Vec<usize> = adjacency[var].iter().copied().filter(|v| remaining.contains(v)).collect();
+            for (i, &a) in neighbors.iter().enumerate() {
+                for &b in &neighbors[i + 1..] {
+                    adjacency[a].insert(b);
+                    adjacency[b].insert(a);
+                }
+            }
+            let mut clique = neighbors;
+            clique.push(var);
+            clique.sort_unstable();
+            candidates.push(clique);
+            remaining.remove(&var);
+        }
+
+        // 3. Quedarse con los cliques maximales
+        let mut cliques: Vec<Vec<usize>> = Vec::new();
+        for candidate in candidates {
+            let contained = |big: &Vec<usize>| candidate.iter().all(|v| big.binary_search(v).is_ok());
+            if !cliques.iter().any(contained) {
+                cliques.retain(|c| !c.iter().all(|v| candidate.binary_search(v).is_ok()));
+                cliques.push(candidate);
+            }
+        }
+
+        // 4. Árbol de expansión máximo (Kruskal) sobre el tamaño de los separadores.
+        // Los pares sin variables en común también entran (peso 0) para unir componentes.
+        let mut pairs: Vec<(usize, usize, usize)> = Vec::new();
+        for i in 0..cliques.len() {
+            for j in i + 1..cliques.len() {
+                let shared = cliques[i].iter().filter(|v| cliques[j].binary_search(v).is_ok()).count();
+                pairs.push((shared, i, j));
+            }
+        }
+        pairs.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        let mut component: Vec<usize> = (0..cliques.len()).collect();
+        let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); cliques.len()];
+        for (_, i, j) in pairs {
+            let (ci, cj) = (find(&mut component, i), find(&mut component, j));
+            if ci != cj {
+                component[ci] = cj;
+                neighbors[i].push(j);
+                neighbors[j].push(i);
+            }
+        }
+
+        // 5. Potenciales: cada CPT va al clique más pequeño que contiene su familia
+        let mut potentials: Vec<Factor> = cliques
+            .iter()
+            .map(|c| {
+                let cards = c.iter().map(|v| tables.card(*v)).collect();
+                Factor::from_fn(c.clone(), cards, |_| 1.0)
+            })
+            .collect();
+        for var in 0..n {
+            let cpt = tables.cpt_factor(var);
+            let target = smallest_clique_with(&cliques, &cpt.vars);
+            potentials[target] = potentials[target].product(&cpt);
+        }
+
+        let home = (0..n).map(|v| smallest_clique_with(&cliques, &[v])).collect();
+
+        JunctionTree { cliques, potentials, neighbors, home, last: RefCell::new(None) }
+    }
+
+    /// Calibra el árbol con la evidencia dada, reutilizando el resultado anterior
+    /// si la evidencia es la misma.
+    pub fn calibrated(&self, observed: &[Option<usize>]) -> Rc<Calibration> {
+        if let Some((cached_evidence, calibration)) = self.last.borrow().as_ref()
+            && cached_evidence.as_slice() == observed
+        {
+            return Rc::clone(calibration);
+        }
+
+        let calibration = Rc::new(self.calibrate(observed));
+        *self.last.borrow_mut() = Some((observed.to_vec(), Rc::clone(&calibration)));
+        calibration
+    }
+
+    // Shafer-Shenoy: recolección hacia la raíz (clique 0) y distribución desde ella.
+    fn calibrate(&self, observed: &[Option<usize>]) -> Calibration {
+        let mut potentials = self.potentials.clone();
+        for (var, state) in observed.iter().enumerate() {
+            if let Some(state) = state {
+                potentials[self.home[var]].observe(var, *state);
+            }
+        }
+
+        // Orden DFS desde la raíz: padre de cada clique en el árbol
+        let k = self.cliques.len();
+        let mut order = Vec::with_capacity(k);
+        let mut parent = vec![usize::MAX; k];
+        let mut stack: Vec<usize> = (0..k.min(1)).collect();
+        let mut visited = vec![false; k];
+        while let Some(c) = stack.pop() {
+            if visited[c] {
+                continue;
+            }
+            visited[c] = true;
+            order.push(c);
+            for &nb in &self.neighbors[c] {
+                if !visited[nb] {
+                    parent[nb] = c;
+                    stack.push(nb);
+                }
+            }
+        }
+
+        let mut messages: HashMap<(usize, usize), Factor> = HashMap::new();
+        let send = |from: usize, to: usize, messages: &HashMap<(usize, usize), Factor>| -> Factor {
+            let mut product = potentials[from].clone();
+            for &nb in &self.neighbors[from] {
+                if nb != to {
+                    product = product.product(&messages[&(nb, from)]);
+                }
+            }
+            let separator: Vec<usize> = self.cliques[from]
+                .iter()
+                .copied()
+                .filter(|v| self.cliques[to].binary_search(v).is_ok())
+                .collect();
+            product.project(&separator)
+        };
+
+        // Recolección (hojas → raíz)
+        for &c in order.iter().rev() {
+            if parent[c] != usize::MAX {
+                let message = send(c, parent[c], &messages);
+                messages.insert((c, parent[c]), message);
+            }
+        }
+        // Distribución (raíz → hojas)
+        for &c in &order {
+            for &nb in &self.neighbors[c] {
+                if parent[nb] == c {
+                    let message = send(c, nb, &messages);
+                    messages.insert((c, nb), message);
+                }
+            }
+        }
+
+        let beliefs: Vec<Factor> = (0..k)
+            .map(|c| {
+                self.neighbors[c]
+                    .iter()
+                    .fold(potentials[c].clone(), |acc, nb| acc.product(&messages[&(*nb, c)]))
+            })
+            .collect();
+
+        let evidence_probability = beliefs.first().map_or(1.0, Factor::total);
+        Calibration { beliefs, evidence_probability }
+    }
+
+    /// Marginal normalizada de `var`.
+    pub fn marginal(&self, calibration: &Calibration, var: usize) -> Vec<f64> {
+        calibration.beliefs[self.home[var]].project(&[var]).normalized().values
+    }
+}
+
+impl Calibration {
+    pub fn evidence_probability(&self) -> f64 {
+        self.evidence_probability
+    }
+}
+
+/// Posterior de `query` usando el árbol compilado (misma firma que `variable_elimination`).
+pub(crate) fn junction_tree_posterior(
+    tree: &JunctionTree,
+    tables: &NetworkTables,
+    evidence: &HashMap<usize, State>,
+    query: usize,
+) -> Result<HashMap<State, f64>, String> {
+    let query_index = tables
+        .index_of(query)
+        .ok_or_else(|| format!("Query node {} not found", query))?;
+
+    let Some(observed) = tables.evidence_vector(evidence) else {
+        return Ok(HashMap::new());
+    };
+
+    let calibration = tree.calibrated(&observed);
+    if calibration.evidence_probability() <= 0.0 {
+        return Ok(HashMap::new());
+    }
+    Ok(tables.to_distribution(query_index, &tree.marginal(&calibration, query_index)))
+}
+
+fn min_fill_variable(adjacency: &[BTreeSet<usize>], remaining: &BTreeSet<usize>, tables: &NetworkTables) -> Option<usize> {
+    remaining
+        .iter()
+        .copied()
+        .min_by_key(|&var| {
+            let neighbors: Vec<usize> = adjacency[var].iter().copied().filter(|v| remaining.contains(v)).collect();
+            let mut fill = 0;
+            for (i, a) in neighbors.iter().enumerate() {
+                for b in &neighbors[i + 1..] {
+                    if !adjacency[*a].contains(b) {
+                        fill += 1;
+                    }
+                }
+            }
+            let weight: usize = neighbors.iter().map(|v| tables.card(*v)).product::<usize>() * tables.card(var);
+            (fill, weight)
+        })
+}
+
+fn smallest_clique_with(cliques: &[Vec<usize>], vars: &[usize]) -> usize {
+    (0..cliques.len())
+        .filter(|&c| vars.iter().all(|v| cliques[c].binary_search(v).is_ok()))
+        .min_by_key(|&c| cliques[c].len())
+        .expect("Every family is covered by some clique after triangulation")
+}
+
+fn find(component: &mut [usize], mut i: usize) -> usize {
+    while component[i] != i {
+        component[i] = component[component[i]];
+        i = component[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::build_network_internal;
+    use crate::inference::variable_elimination::eliminate_all_but;
+
+    #[test]
+    fn test_junction_tree_matches_variable_elimination() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let tree = JunctionTree::compile(&tables);
+
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("Gas_sensor").unwrap(), State::Value("bajo".to_string()));
+        evidence.insert(bn.get_id_from_name("Presion_sensor").unwrap(), State::Value("alta".to_string()));
+        let observed = tables.evidence_vector(&evidence).unwrap();
+
+        let calibration = tree.calibrated(&observed);
+        for var in 0..tables.len() {
+            let exact = eliminate_all_but(&tables, &observed, &[var]);
+            let expected = exact.clone().normalized().values;
+            let actual = tree.marginal(&calibration, var);
+            for (p, q) in expected.iter().zip(&actual) {
+                assert!((p - q).abs() < 1e-12, "Nodo {}: VE {:?} vs JT {:?}", var, expected, actual);
+            }
+            assert!((exact.total() - calibration.evidence_probability()).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_junction_tree_structure_and_cache() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let tree = JunctionTree::compile(&tables);
+
+        // Es un árbol: k cliques y k-1 aristas
+        let edges: usize = tree.neighbors.iter().map(Vec::len).sum::<usize>() / 2;
+        assert_eq!(edges, tree.cliques.len() - 1);
+
+        // Misma evidencia → misma calibración en caché
+        let observed = vec![None; tables.len()];
+        let first = tree.calibrated(&observed);
+        let second = tree.calibrated(&observed);
+        assert!(Rc::ptr_eq(&first, &second), "La calibración debería reutilizarse");
+        assert!((first.evidence_probability() - 1.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,207 @@
+// Motores de inferencia propios del crate (complementan el muestreo de suma_core).
+
+pub(crate) mod factor;
+pub(crate) mod gibbs;
+pub(crate) mod junction_tree;
+pub(crate) mod mpe;
+pub(crate) mod sampling;
+pub(crate) mod tables;
+pub(crate) mod variable_elimination;
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+
+/// Motor usado por `BiodigestorModel::infer`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InferenceAlgorithm {
+    /// Muestreo por ponderación de verosimilitud (aproximado).
+    #[default]
+    LikelihoodWeighting,
+    /// Eliminación de variables (exacto y determinista).
+    Exact,
+    /// Árbol de cliques compilado al construir el modelo (exacto, con caché por evidencia).
+    JunctionTree,
+    /// Muestreo de Gibbs con varias cadenas (robusto ante evidencia muy improbable).
+    Gibbs,
+}
+
+/// Opciones de inferencia. Desde JS se pasan como objeto plano, p. ej.
+/// `{ algorithm: "likelihood_weighting", samples: 20000, seed: 42, max_time_ms: 50, tolerance: 0.001 }`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct InferenceOptions {
+    pub algorithm: InferenceAlgorithm,
+    /// Número de muestras de los motores de muestreo.
+    pub samples: usize,
+    /// Semilla del generador; con la misma semilla el resultado es idéntico bit a bit.
+    pub seed: Option<u64>,
+    /// Presupuesto de tiempo en milisegundos. Si se agota, se devuelve lo muestreado hasta entonces
+    /// (y el resultado deja de ser reproducible aunque haya semilla).
+    pub max_time_ms: Option<f64>,
+    /// Muestreo adaptativo: detenerse cuando la posterior cambie menos que esto entre
+    /// dos bloques consecutivos (y haya al menos unos pocos bloques). En ese modo
+    /// `samples` es el tope de muestras.
+    pub tolerance: Option<f64>,
+    /// Tamaño de bloque del muestreo adaptativo.
+    pub chunk_size: usize,
+    /// Gibbs: barridos descartados al inicio de cada cadena.
+    pub burn_in: usize,
+    /// Gibbs: se conserva un barrido de cada `thinning`.
+    pub thinning: usize,
+    /// Gibbs: número de cadenas independientes (al menos 2 para calcular R-hat).
+    pub chains: usize,
+}
+
+impl Default for InferenceOptions {
+    fn default() -> Self {
+        InferenceOptions {
+            algorithm: InferenceAlgorithm::default(),
+            samples: 10_000,
+            seed: None,
+            max_time_ms: None,
+            tolerance: None,
+            chunk_size: 1_000,
+            burn_in: 1_000,
+            thinning: 1,
+            chains: 4,
+        }
+    }
+}
+
+impl InferenceOptions {
+    /// Rechaza opciones con las que los motores de muestreo no pueden dar un resultado.
+    pub fn check(&self) -> Result<(), String> {
+        if self.samples == 0 {
+            return Err("Inference needs at least one sample".to_string());
+        }
+        if self.chunk_size == 0 {
+            return Err("chunk_size must be at least 1".to_string());
+        }
+        if self.chains == 0 || self.thinning == 0 {
+            return Err(format!("Gibbs needs chains and thinning of at least 1, got {} and {}", self.chains, self.thinning));
+        }
+        if let Some(tolerance) = self.tolerance
+            && !(tolerance.is_finite() && tolerance > 0.0)
+        {
+            return Err(format!("Sampling tolerance must be a positive number, got {}", tolerance));
+        }
+        // Un presupuesto NaN no vence nunca y uno nulo corta antes de la primera muestra
+        if let Some(budget) = self.max_time_ms
+            && !(budget.is_finite() && budget > 0.0)
+        {
+            return Err(format!("max_time_ms must be a positive number, got {}", budget));
+        }
+        Ok(())
+    }
+}
+
+/// Resultado de una consulta: la distribución y, si se muestreó, sus diagnósticos.
+#[derive(Debug, Clone, Serialize)]
+pub struct InferenceResult {
+    pub algorithm: InferenceAlgorithm,
+    pub probabilities: HashMap<String, f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SamplingDiagnostics>,
+    pub evidence: EvidenceLikelihood,
+}
+
+/// P(evidencia) bajo el modelo y su logaritmo natural. Un valor muy bajo indica lecturas
+/// que el modelo no explica (a menudo un sensor averiado más que un fallo del proceso).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct EvidenceLikelihood {
+    pub probability: f64,
+    pub log_probability: f64,
+    /// Solo si se estimó por ponderación de verosimilitud (media de los pesos).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub std_error: Option<f64>,
+}
+
+impl EvidenceLikelihood {
+    pub fn exact(probability: f64) -> Self {
+        EvidenceLikelihood { probability, log_probability: probability.ln(), std_error: None }
+    }
+
+    pub fn estimated(probability: f64, std_error: f64) -> Self {
+        EvidenceLikelihood { probability, log_probability: probability.ln(), std_error: Some(std_error) }
+    }
+}
+
+/// Fiabilidad de una estimación por muestreo.
+#[derive(Debug, Clone, Serialize)]
+pub struct SamplingDiagnostics {
+    pub samples: usize,
+    /// Tamaño efectivo de muestra de Kish, (Σw)² / Σw².
+    pub effective_sample_size: f64,
+    pub intervals: HashMap<String, ProbabilityInterval>,
+    /// Solo en modo adaptativo: si la posterior convergió antes de los topes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converged: Option<bool>,
+    /// Solo Gibbs con varias cadenas: R-hat de Gelman-Rubin (máximo sobre estados; ~1 indica mezcla).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r_hat: Option<f64>,
+}
+
+/// Error estándar e intervalo normal al 95% (recortado a [0, 1]) de una probabilidad.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ProbabilityInterval {
+    pub std_error: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl ProbabilityInterval {
+    pub fn normal_95(probability: f64, std_error: f64) -> Self {
+        ProbabilityInterval {
+            std_error,
+            lower: (probability - 1.96 * std_error).max(0.0),
+            upper: (probability + 1.96 * std_error).min(1.0),
+        }
+    }
+}
+
+/// Asignación conjunta de un conjunto de nodos (los no observados en MPE, los pedidos en MAP).
+#[derive(Debug, Clone, Serialize)]
+pub struct Explanation {
+    pub assignment: HashMap<String, String>,
+    /// P(asignación | evidencia).
+    pub probability: f64,
+    /// P(asignación, evidencia).
+    pub joint_probability: f64,
+}
+
+/// Resultado de `most_probable_explanation`: la mejor explicación y las siguientes en orden.
+#[derive(Debug, Clone, Serialize)]
+pub struct MostProbableExplanation {
+    pub best: Explanation,
+    pub alternatives: Vec<Explanation>,
+    pub evidence: EvidenceLikelihood,
+}
+
+/// Resultado de `map_query`: distribución conjunta sobre `variables` (de mayor a menor
+/// probabilidad, el resto de nodos sumados) y su moda.
+#[derive(Debug, Clone, Serialize)]
+pub struct MapResult {
+    pub variables: Vec<String>,
+    pub best: Explanation,
+    pub distribution: Vec<Explanation>,
+    pub evidence: EvidenceLikelihood,
+}
+
+/// Posterior conjunta de `infer_joint`: una fila por combinación de estados de `variables`.
+#[derive(Debug, Clone, Serialize)]
+pub struct JointPosterior {
+    pub variables: Vec<String>,
+    /// Condiciones de la consulta (vacío si no es condicional).
+    pub given: HashMap<String, String>,
+    pub entries: Vec<JointEntry>,
+    /// P(evidencia, condiciones).
+    pub evidence: EvidenceLikelihood,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JointEntry {
+    /// Un estado por variable, en el orden de `JointPosterior::variables`.
+    pub states: Vec<String>,
+    pub probability: f64,
+}
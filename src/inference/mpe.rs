@@ -0,0 +1,226 @@
+use std::cmp::Ordering;
+
+use super::factor::Factor;
+use super::tables::NetworkTables;
+use super::variable_elimination::{elimination_order, evidence_factors};
+
+// Explicación más probable (MPE) por max-product sobre un semianillo de las k
+// mejores: cada entrada de un factor guarda hasta k candidatos (valor y las
+// asignaciones de las variables ya maximizadas). Con k = 1 es max-product clásico.
+
+/// Asignación completa (un estado por nodo) y su probabilidad conjunta P(x, e).
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct RankedAssignment {
+    pub states: Vec<usize>,
+    pub joint: f64,
+}
+
+#[derive(Debug, Clone)]
+struct Candidate {
+    value: f64,
+    /// Pares (variable, estado) ordenados por variable.
+    trace: Vec<(usize, usize)>,
+}
+
+struct KBestFactor {
+    vars: Vec<usize>,
+    cards: Vec<usize>,
+    entries: Vec<Vec<Candidate>>,
+}
+
+impl KBestFactor {
+    fn from_factor(factor: &Factor) -> Self {
+        let entries = factor
+            .values
+            .iter()
+            .map(|v| if *v > 0.0 { vec![Candidate { value: *v, trace: Vec::new() }] } else { Vec::new() })
+            .collect();
+        KBestFactor { vars: factor.vars.clone(), cards: factor.cards.clone(), entries }
+    }
+
+    fn index_of(&self, vars: &[usize], assignment: &[usize]) -> usize {
+        self.vars.iter().zip(&self.cards).fold(0, |index, (v, card)| {
+            let pos = vars.binary_search(v).expect("Operand var is in the product scope");
+            index * card + assignment[pos]
+        })
+    }
+
+    fn product(&self, other: &KBestFactor, k: usize) -> KBestFactor {
+        let mut vars: Vec<usize> = self.vars.iter().chain(&other.vars).copied().collect();
+        vars.sort_unstable();
+        vars.dedup();
+        let cards: Vec<usize> = vars
+            .iter()
+            .map(|v| {
+                let own = self.vars.iter().position(|x| x == v).map(|p| self.cards[p]);
+                own.or_else(|| other.vars.iter().position(|x| x == v).map(|p| other.cards[p])).unwrap_or(1)
+            })
+            .collect();
+
+        let len: usize = cards.iter().product();
+        let mut entries = Vec::with_capacity(len);
+        let mut assignment = vec![0; vars.len()];
+        for _ in 0..len {
+            let a = &self.entries[self.index_of(&vars, &assignment)];
+            let b = &other.entries[other.index_of(&vars, &assignment)];
+            let mut combined: Vec<Candidate> = Vec::with_capacity(a.len() * b.len());
+            for x in a {
+                for y in b {
+                    let mut trace = x.trace.clone();
+                    trace.extend_from_slice(&y.trace);
+                    trace.sort_unstable();
+                    combined.push(Candidate { value: x.value * y.value, trace });
+                }
+            }
+            entries.push(keep_best(combined, k));
+            super::factor::increment(&mut assignment, &cards);
+        }
+
+        KBestFactor { vars, cards, entries }
+    }
+
+    fn max_out(&self, var: usize, k: usize) -> KBestFactor {
+        let Some(pos) = self.vars.iter().position(|v| *v == var) else {
+            return KBestFactor { vars: self.vars.clone(), cards: self.cards.clone(), entries: self.entries.clone() };
+        };
+
+        let outer: usize = self.cards[..pos].iter().product();
+        let card = self.cards[pos];
+        let inner: usize = self.cards[pos + 1..].iter().product();
+
+        let mut entries = Vec::with_capacity(outer * inner);
+        for o in 0..outer {
+            for i in 0..inner {
+                let mut merged = Vec::new();
+                for s in 0..card {
+                    for candidate in &self.entries[(o * card + s) * inner + i] {
+                        let mut trace = candidate.trace.clone();
+                        trace.push((var, s));
+                        trace.sort_unstable();
+                        merged.push(Candidate { value: candidate.value, trace });
+                    }
+                }
+                entries.push(keep_best(merged, k));
+            }
+        }
+
+        let mut vars = self.vars.clone();
+        let mut cards = self.cards.clone();
+        vars.remove(pos);
+        cards.remove(pos);
+        KBestFactor { vars, cards, entries }
+    }
+}
+
+// Orden determinista: mayor valor primero y, a igualdad, la traza lexicográficamente menor.
+fn keep_best(mut candidates: Vec<Candidate>, k: usize) -> Vec<Candidate> {
+    candidates.sort_by(|a, b| {
+        b.value
+            .partial_cmp(&a.value)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.trace.cmp(&b.trace))
+    });
+    candidates.truncate(k);
+    candidates
+}
+
+/// Las `k` asignaciones completas más probables compatibles con la evidencia,
+/// de mayor a menor P(x, e). Vacío si la evidencia es imposible.
+pub(crate) fn top_k_assignments(tables: &NetworkTables, observed: &[Option<usize>], k: usize) -> Vec<RankedAssignment> {
+    let k = k.max(1);
+    let factors = evidence_factors(tables, observed);
+    let scopes: Vec<Vec<usize>> = factors.iter().map(|f| f.vars.clone()).collect();
+    let mut factors: Vec<KBestFactor> = factors.iter().map(KBestFactor::from_factor).collect();
+
+    for var in elimination_order(tables, scopes, &[]) {
+        let (related, rest): (Vec<KBestFactor>, Vec<KBestFactor>) =
+            factors.into_iter().partition(|f| f.vars.contains(&var));
+        factors = rest;
+
+        let unit = KBestFactor {
+            vars: Vec::new(),
+            cards: Vec::new(),
+            entries: vec![vec![Candidate { value: 1.0, trace: Vec::new() }]],
+        };
+        let product = related.iter().fold(unit, |acc, f| acc.product(f, k));
+        factors.push(product.max_out(var, k));
+    }
+
+    let unit = KBestFactor {
+        vars: Vec::new(),
+        cards: Vec::new(),
+        entries: vec![vec![Candidate { value: 1.0, trace: Vec::new() }]],
+    };
+    let root = factors.iter().fold(unit, |acc, f| acc.product(f, k));
+
+    root.entries
+        .into_iter()
+        .next()
+        .unwrap_or_default()
+        .into_iter()
+        .map(|candidate| {
+            let mut states = vec![0; tables.len()];
+            for (var, state) in candidate.trace {
+                states[var] = state;
+            }
+            RankedAssignment { states, joint: candidate.value }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::build_network_internal;
+    use crate::inference::factor::increment;
+    use suma_core::core::probability::bayes::BN_base::State;
+
+    // Fuerza bruta sobre todas las asignaciones (la red tiene ~350k configuraciones).
+    fn brute_force(tables: &NetworkTables, observed: &[Option<usize>], k: usize) -> Vec<f64> {
+        let cards: Vec<usize> = (0..tables.len()).map(|v| tables.card(v)).collect();
+        let total: usize = cards.iter().product();
+        let mut assignment = vec![0; tables.len()];
+        let mut joints = Vec::new();
+        for _ in 0..total {
+            let compatible = observed.iter().zip(&assignment).all(|(o, a)| o.is_none_or(|s| s == *a));
+            if compatible {
+                let joint: f64 = (0..tables.len())
+                    .map(|v| tables.cpt_row(v, tables.parent_row(v, &assignment))[assignment[v]])
+                    .product();
+                joints.push(joint);
+            }
+            increment(&mut assignment, &cards);
+        }
+        joints.sort_by(|a, b| b.partial_cmp(a).unwrap());
+        joints.truncate(k);
+        joints
+    }
+
+    #[test]
+    fn test_top_k_matches_brute_force() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+
+        let mut observed = vec![None; tables.len()];
+        for (sensor, value) in [("Gas_sensor", "bajo"), ("Flow_sensor", "alto"), ("T_sensor", "baja")] {
+            let index = tables.index_of(bn.get_id_from_name(sensor).unwrap()).unwrap();
+            observed[index] = tables.state_index(index, &State::Value(value.to_string()));
+        }
+
+        let ranked = top_k_assignments(&tables, &observed, 5);
+        let expected = brute_force(&tables, &observed, 5);
+
+        assert_eq!(ranked.len(), 5);
+        for (got, want) in ranked.iter().zip(&expected) {
+            assert!((got.joint - want).abs() < 1e-15, "{} vs {}", got.joint, want);
+        }
+        // La evidencia se respeta en todas las explicaciones
+        for explanation in &ranked {
+            for (var, state) in observed.iter().enumerate() {
+                if let Some(state) = state {
+                    assert_eq!(explanation.states[var], *state);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+use suma_core::core::probability::bayes::BN_base::State;
+
+use super::tables::NetworkTables;
+use super::{EvidenceLikelihood, InferenceOptions};
+
+// Muestreo propio del crate: a diferencia de suma_core, el generador se puede
+// sembrar y el recorrido de la red es determinista, así que una misma semilla
+// reproduce la distribución bit a bit.
+
+/// Generador SplitMix64. Pequeño, rápido y suficiente para muestreo.
+pub(crate) struct SampleRng {
+    state: u64,
+}
+
+impl SampleRng {
+    pub fn new(seed: u64) -> Self {
+        SampleRng { state: seed }
+    }
+
+    /// Semilla explícita o, si no hay, una derivada del reloj.
+    pub fn from_options(options: &InferenceOptions) -> Self {
+        SampleRng::new(options.seed.unwrap_or_else(entropy_seed))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniforme en [0, 1) con 53 bits de precisión.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Índice muestreado según `probs` (no hace falta que sumen exactamente 1).
+    pub fn categorical(&mut self, probs: &[f64]) -> usize {
+        let total: f64 = probs.iter().sum();
+        let target = self.next_f64() * total;
+        let mut cumulative = 0.0;
+        for (i, p) in probs.iter().enumerate() {
+            cumulative += p;
+            if target < cumulative {
+                return i;
+            }
+        }
+        // Redondeo: último estado con probabilidad positiva
+        probs.iter().rposition(|p| *p > 0.0).unwrap_or(0)
+    }
+}
+
+// Semilla no reproducible: hora actual más un contador para que dos llamadas
+// en el mismo milisegundo no compartan secuencia.
+fn entropy_seed() -> u64 {
+    use std::cell::Cell;
+    thread_local! {
+        static CALLS: Cell<u64> = const { Cell::new(0) };
+    }
+    let calls = CALLS.with(|c| {
+        c.set(c.get() + 1);
+        c.get()
+    });
+    wall_clock_ms().to_bits() ^ calls.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+#[cfg(target_arch = "wasm32")]
+use now_ms as wall_clock_ms;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn wall_clock_ms() -> f64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64() * 1000.0)
+}
+
+/// Milisegundos de un reloj monotónico (en wasm, `Date.now()`).
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn now_ms() -> f64 {
+    js_sys::Date::now()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn now_ms() -> f64 {
+    use std::sync::OnceLock;
+    use std::time::Instant;
+    static START: OnceLock<Instant> = OnceLock::new();
+    START.get_or_init(Instant::now).elapsed().as_secs_f64() * 1000.0
+}
+
+// Cada cuántas muestras se consulta el reloj cuando hay presupuesto de tiempo.
+const TIME_CHECK_INTERVAL: usize = 256;
+
+// Bloques mínimos antes de dar por convergido el muestreo adaptativo: con muy pocas
+// muestras dos estimaciones seguidas pueden coincidir por casualidad.
+const MIN_CONVERGENCE_CHUNKS: usize = 4;
+
+/// Pesos acumulados por estado del nodo consultado.
+pub(crate) struct WeightedTally {
+    pub weights: Vec<f64>,
+    /// Suma de pesos al cuadrado por estado (para el error estándar).
+    pub squared_weights: Vec<f64>,
+    pub total_weight: f64,
+    pub samples: usize,
+    /// `Some` solo en modo adaptativo: si se alcanzó la tolerancia antes de los topes.
+    pub converged: Option<bool>,
+}
+
+/// Estimación muestreada con sus diagnósticos, indexada por estado.
+pub(crate) struct SampledPosterior {
+    pub distribution: HashMap<State, f64>,
+    pub std_errors: HashMap<State, f64>,
+    pub effective_sample_size: f64,
+    pub samples: usize,
+    pub converged: Option<bool>,
+    /// R-hat de Gelman-Rubin (solo Gibbs con varias cadenas).
+    pub r_hat: Option<f64>,
+    /// Estimación de P(evidencia) (solo ponderación de verosimilitud).
+    pub evidence: Option<EvidenceLikelihood>,
+}
+
+impl WeightedTally {
+    fn new(card: usize) -> Self {
+        WeightedTally {
+            weights: vec![0.0; card],
+            squared_weights: vec![0.0; card],
+            total_weight: 0.0,
+            samples: 0,
+            converged: None,
+        }
+    }
+
+    fn add(&mut self, state: usize, weight: f64) {
+        self.weights[state] += weight;
+        self.squared_weights[state] += weight * weight;
+        self.total_weight += weight;
+    }
+
+    /// Tamaño efectivo de muestra de Kish: (Σw)² / Σw².
+    pub fn effective_sample_size(&self) -> f64 {
+        let squared: f64 = self.squared_weights.iter().sum();
+        if squared > 0.0 { self.total_weight * self.total_weight / squared } else { 0.0 }
+    }
+
+    /// P(evidencia) ≈ media de los pesos, con su error estándar √(Var(w) / n).
+    pub fn evidence_likelihood(&self) -> EvidenceLikelihood {
+        let n = self.samples as f64;
+        let mean = self.total_weight / n;
+        let mean_squared: f64 = self.squared_weights.iter().sum::<f64>() / n;
+        EvidenceLikelihood::estimated(mean, ((mean_squared - mean * mean).max(0.0) / n).sqrt())
+    }
+
+    pub fn probabilities(&self) -> Vec<f64> {
+        self.weights.iter().map(|w| w / self.total_weight).collect()
+    }
+
+    /// Error estándar (método delta) del estimador autonormalizado de cada estado:
+    /// Var(p̂ₛ) ≈ Σᵢ wᵢ² (1[xᵢ = s] − p̂ₛ)² / (Σw)².
+    pub fn std_errors(&self) -> Vec<f64> {
+        let total_squared: f64 = self.squared_weights.iter().sum();
+        self.probabilities()
+            .iter()
+            .zip(&self.squared_weights)
+            .map(|(p, own_squared)| {
+                let spread = own_squared * (1.0 - p).powi(2) + (total_squared - own_squared) * p * p;
+                spread.max(0.0).sqrt() / self.total_weight
+            })
+            .collect()
+    }
+}
+
+/// Ponderación de verosimilitud sobre la vista indexada de la red.
+///
+/// Respeta `samples`, `seed` y `max_time_ms` de las opciones. Si se agota el
+/// presupuesto de tiempo, devuelve lo acumulado hasta ese momento.
+///
+/// Con `tolerance`, muestrea en bloques de `chunk_size` y se detiene cuando la
+/// posterior cambia menos que la tolerancia (máxima diferencia absoluta por
+/// estado) entre dos bloques consecutivos, tras al menos `MIN_CONVERGENCE_CHUNKS`
+/// bloques; `samples` pasa a ser el tope. Las opciones se validan con `check()`.
+pub(crate) fn likelihood_weighting_tally(
+    tables: &NetworkTables,
+    observed: &[Option<usize>],
+    query: usize,
+    options: &InferenceOptions,
+) -> WeightedTally {
+    let order = tables.topological_order();
+    let mut rng = SampleRng::from_options(options);
+    let deadline = options.max_time_ms.map(|budget| now_ms() + budget);
+
+    let mut tally = WeightedTally::new(tables.card(query));
+    let mut sample = vec![0usize; tables.len()];
+    let chunk_size = options.chunk_size;
+    let mut previous: Option<Vec<f64>> = None;
+
+    while tally.samples < options.samples {
+        if let Some(deadline) = deadline
+            && tally.samples.is_multiple_of(TIME_CHECK_INTERVAL)
+            && tally.samples > 0
+            && now_ms() >= deadline
+        {
+            break;
+        }
+
+        let mut weight = 1.0;
+        for &node in &order {
+            let row = tables.parent_row(node, &sample);
+            let probs = tables.cpt_row(node, row);
+            match observed[node] {
+                Some(state) => {
+                    sample[node] = state;
+                    weight *= probs[state];
+                }
+                None => sample[node] = rng.categorical(probs),
+            }
+        }
+
+        tally.samples += 1;
+        if weight > 0.0 {
+            tally.add(sample[query], weight);
+        }
+
+        if let Some(tolerance) = options.tolerance
+            && tally.samples.is_multiple_of(chunk_size)
+            && tally.total_weight > 0.0
+        {
+            let current = tally.probabilities();
+            if let Some(previous) = &previous {
+                let change = current.iter().zip(previous).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+                if change < tolerance && tally.samples >= MIN_CONVERGENCE_CHUNKS * chunk_size {
+                    tally.converged = Some(true);
+                    return tally;
+                }
+            }
+            previous = Some(current);
+        }
+    }
+
+    if options.tolerance.is_some() {
+        tally.converged = Some(false);
+    }
+    tally
+}
+
+/// Posterior de `query` por ponderación de verosimilitud con tamaño efectivo de
+/// muestra y errores estándar. `None` si ninguna muestra tuvo peso positivo.
+pub(crate) fn likelihood_weighting_posterior(
+    tables: &NetworkTables,
+    evidence: &HashMap<usize, State>,
+    query: usize,
+    options: &InferenceOptions,
+) -> Result<Option<SampledPosterior>, String> {
+    let query_index = tables
+        .index_of(query)
+        .ok_or_else(|| format!("Query node {} not found", query))?;
+
+    let Some(observed) = tables.evidence_vector(evidence) else {
+        return Ok(None);
+    };
+
+    let tally = likelihood_weighting_tally(tables, &observed, query_index, options);
+    if tally.total_weight <= 0.0 {
+        return Ok(None);
+    }
+
+    Ok(Some(SampledPosterior {
+        distribution: tables.to_distribution(query_index, &tally.probabilities()),
+        std_errors: tables.to_distribution(query_index, &tally.std_errors()),
+        effective_sample_size: tally.effective_sample_size(),
+        samples: tally.samples,
+        converged: tally.converged,
+        r_hat: None,
+        evidence: Some(tally.evidence_likelihood()),
+    }))
+}
+
+/// Solo la distribución, con la misma firma que `variable_elimination` (usado por los tests).
+#[cfg(test)]
+pub(crate) fn likelihood_weighting(
+    tables: &NetworkTables,
+    evidence: &HashMap<usize, State>,
+    query: usize,
+    options: &InferenceOptions,
+) -> Result<HashMap<State, f64>, String> {
+    Ok(likelihood_weighting_posterior(tables, evidence, query, options)?
+        .map(|sampled| sampled.distribution)
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::build_network_internal;
+
+    #[test]
+    fn test_seeded_runs_are_identical() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("Flow_sensor").unwrap(), State::Value("alto".to_string()));
+        let target = bn.get_id_from_name("EstadoOperativo").unwrap();
+
+        let options = InferenceOptions { samples: 5_000, seed: Some(42), ..InferenceOptions::default() };
+        let first = likelihood_weighting(&tables, &evidence, target, &options).unwrap();
+        let second = likelihood_weighting(&tables, &evidence, target, &options).unwrap();
+
+        for (state, p) in &first {
+            assert_eq!(p.to_bits(), second[state].to_bits(), "Estado {:?} difiere entre corridas", state);
+        }
+
+        let other_seed = InferenceOptions { seed: Some(7), ..options };
+        let third = likelihood_weighting(&tables, &evidence, target, &other_seed).unwrap();
+        assert_ne!(first, third, "Semillas distintas deberían dar estimaciones distintas");
+    }
+
+    #[test]
+    fn test_sample_count_and_time_budget() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let observed = vec![None; tables.len()];
+
+        let options = InferenceOptions { samples: 1_234, seed: Some(1), ..InferenceOptions::default() };
+        let tally = likelihood_weighting_tally(&tables, &observed, 0, &options);
+        assert_eq!(tally.samples, 1_234);
+
+        // Presupuesto mínimo: se corta en la primera comprobación del reloj
+        let options = InferenceOptions { samples: 1_000_000, seed: Some(1), max_time_ms: Some(1e-9), ..InferenceOptions::default() };
+        let tally = likelihood_weighting_tally(&tables, &observed, 0, &options);
+        assert_eq!(tally.samples, TIME_CHECK_INTERVAL);
+
+        // Un presupuesto nulo, negativo o NaN se rechaza antes de muestrear
+        for budget in [0.0, -5.0, f64::NAN, f64::INFINITY] {
+            assert!(InferenceOptions { max_time_ms: Some(budget), ..options.clone() }.check().is_err(), "{}", budget);
+        }
+        assert!(options.check().is_ok());
+    }
+
+    #[test]
+    fn test_adaptive_sampling_stops_on_convergence() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let target = bn.get_id_from_name("EstadoOperativo").unwrap();
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("Presion_sensor").unwrap(), State::Value("baja".to_string()));
+
+        // Tolerancia alcanzable: se detiene antes del tope
+        let options = InferenceOptions {
+            samples: 500_000,
+            seed: Some(11),
+            tolerance: Some(1e-3),
+            chunk_size: 2_000,
+            ..InferenceOptions::default()
+        };
+        let sampled = likelihood_weighting_posterior(&tables, &evidence, target, &options).unwrap().unwrap();
+        assert_eq!(sampled.converged, Some(true));
+        assert!(sampled.samples < 500_000 && sampled.samples.is_multiple_of(2_000), "Muestras usadas: {}", sampled.samples);
+
+        let exact = crate::inference::variable_elimination::variable_elimination(&tables, &evidence, target).unwrap();
+        let fuga = State::Value("Fuga".to_string());
+        assert!((sampled.distribution[&fuga] - exact[&fuga]).abs() < 0.02);
+
+        // Bloques diminutos: dos estimaciones iguales al principio no bastan
+        let options_tiny = InferenceOptions { samples: 10_000, tolerance: Some(0.01), chunk_size: 1, ..options.clone() };
+        let sampled = likelihood_weighting_posterior(&tables, &evidence, target, &options_tiny).unwrap().unwrap();
+        assert!(sampled.samples >= MIN_CONVERGENCE_CHUNKS, "Muestras usadas: {}", sampled.samples);
+        assert!(InferenceOptions { chunk_size: 0, ..options_tiny.clone() }.check().is_err());
+        assert!(InferenceOptions { tolerance: Some(-0.01), ..options_tiny.clone() }.check().is_err());
+        assert!(InferenceOptions { tolerance: Some(f64::NAN), ..options_tiny }.check().is_err());
+
+        // Tolerancia inalcanzable: agota el tope y lo informa
+        let options = InferenceOptions { samples: 6_000, tolerance: Some(1e-12), ..options };
+        let sampled = likelihood_weighting_posterior(&tables, &evidence, target, &options).unwrap().unwrap();
+        assert_eq!(sampled.converged, Some(false));
+        assert_eq!(sampled.samples, 6_000);
+    }
+
+    #[test]
+    fn test_effective_sample_size_and_std_errors() {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        let target = bn.get_id_from_name("EstadoMicrobiano").unwrap();
+        let options = InferenceOptions { samples: 20_000, seed: Some(3), ..InferenceOptions::default() };
+
+        // Sin evidencia todos los pesos valen 1: ESS = n y SE = sqrt(p(1-p)/n)
+        let prior = likelihood_weighting_posterior(&tables, &HashMap::new(), target, &options).unwrap().unwrap();
+        assert!((prior.effective_sample_size - 20_000.0).abs() < 1e-6);
+        for (state, p) in &prior.distribution {
+            let expected = (p * (1.0 - p) / 20_000.0).sqrt();
+            assert!((prior.std_errors[state] - expected).abs() < 1e-12, "SE de {:?}", state);
+        }
+
+        // Con evidencia los pesos varían y el ESS cae por debajo de n
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("Gas_sensor").unwrap(), State::Value("bajo".to_string()));
+        evidence.insert(bn.get_id_from_name("pH_sensor").unwrap(), State::Value("acido".to_string()));
+        let sampled = likelihood_weighting_posterior(&tables, &evidence, target, &options).unwrap().unwrap();
+        assert!(sampled.effective_sample_size < 20_000.0 && sampled.effective_sample_size > 100.0);
+
+        // La estimación exacta debería caer dentro de ±3 SE
+        let exact = crate::inference::variable_elimination::variable_elimination(&tables, &evidence, target).unwrap();
+        for (state, p) in &exact {
+            let estimate = sampled.distribution[state];
+            assert!((estimate - p).abs() < 3.0 * sampled.std_errors[state], "{:?}: {} vs {}", state, estimate, p);
+        }
+    }
+}
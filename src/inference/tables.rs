@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use suma_core::core::probability::bayes::BayesianNetwork;
+use suma_core::core::probability::bayes::BN_base::{BayesianNetworkBase, State};
+
+use super::factor::Factor;
+
+// Vista indexada de la red: nodos ordenados por id, estados en el orden
+// declarado y CPTs aplanadas. Los motores exactos trabajan sobre esta vista
+// para no depender del orden de los HashMap internos de suma_core.
+
+pub(crate) struct NodeTable {
+    pub id: usize,
+    pub name: String,
+    pub states: Vec<State>,
+    /// Índices de los padres, en el mismo orden que `get_parent_values` (ids ascendentes).
+    pub parents: Vec<usize>,
+    /// P(estado | padres) en `cpt[fila_padres * states.len() + estado]`.
+    pub cpt: Vec<f64>,
+}
+
+pub(crate) struct NetworkTables {
+    nodes: Vec<NodeTable>,
+    index_of_id: HashMap<usize, usize>,
+}
+
+impl NetworkTables {
+    pub fn from_network(network: &BayesianNetwork) -> Result<Self, String> {
+        let mut ids = network.get_nodes();
+        ids.sort_unstable();
+
+        let index_of_id: HashMap<usize, usize> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+        let mut nodes = Vec::with_capacity(ids.len());
+        for &id in &ids {
+            let name = network
+                .get_name_from_id(id)
+                .cloned()
+                .ok_or_else(|| format!("Node {} has no name", id))?;
+            let cpt = network
+                .get_cpt(id)
+                .ok_or_else(|| format!("Node {} has no CPT", name))?;
+            let states = cpt.possible_values();
+
+            let mut parent_ids = network.get_parents(id);
+            parent_ids.sort_unstable();
+            let parents: Vec<usize> = parent_ids.iter().map(|p| index_of_id[p]).collect();
+
+            nodes.push(NodeTable { id, name, states, parents, cpt: Vec::new() });
+        }
+
+        // Segunda pasada: ya conocemos los estados de todos los padres.
+        for i in 0..nodes.len() {
+            let cpt = network.get_cpt(nodes[i].id).expect("CPT checked above");
+            let parent_cards: Vec<usize> = nodes[i].parents.iter().map(|p| nodes[*p].states.len()).collect();
+            let rows: usize = parent_cards.iter().product();
+
+            let mut table = Vec::with_capacity(rows * nodes[i].states.len());
+            let mut row = vec![0; parent_cards.len()];
+            for _ in 0..rows {
+                let parent_values: Vec<State> = nodes[i]
+                    .parents
+                    .iter()
+                    .zip(&row)
+                    .map(|(p, s)| nodes[*p].states[*s].clone())
+                    .collect();
+                for state in &nodes[i].states {
+                    // Filas ausentes en la CPT se tratan como probabilidad 0, igual que el muestreo.
+                    table.push(cpt.get_probability(&parent_values, state.clone()).unwrap_or(0.0));
+                }
+                super::factor::increment(&mut row, &parent_cards);
+            }
+            nodes[i].cpt = table;
+        }
+
+        Ok(NetworkTables { nodes, index_of_id })
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn nodes(&self) -> &[NodeTable] {
+        &self.nodes
+    }
+
+    pub fn index_of(&self, id: usize) -> Option<usize> {
+        self.index_of_id.get(&id).copied()
+    }
+
+    pub fn card(&self, index: usize) -> usize {
+        self.nodes[index].states.len()
+    }
+
+    pub fn state_index(&self, index: usize, state: &State) -> Option<usize> {
+        self.nodes[index].states.iter().position(|s| s == state)
+    }
+
+    /// Orden topológico de los índices (determinista: a igualdad, el menor índice primero).
+    pub fn topological_order(&self) -> Vec<usize> {
+        let mut pending: Vec<usize> = self.nodes.iter().map(|n| n.parents.len()).collect();
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (child, node) in self.nodes.iter().enumerate() {
+            for &p in &node.parents {
+                children[p].push(child);
+            }
+        }
+
+        let mut ready: std::collections::BTreeSet<usize> = (0..self.nodes.len()).filter(|i| pending[*i] == 0).collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(node) = ready.pop_first() {
+            order.push(node);
+            for &child in &children[node] {
+                pending[child] -= 1;
+                if pending[child] == 0 {
+                    ready.insert(child);
+                }
+            }
+        }
+        order
+    }
+
+    /// Fila de la CPT de `index` para los estados de los padres en `assignment` (indexado por nodo).
+    pub fn parent_row(&self, index: usize, assignment: &[usize]) -> usize {
+        self.nodes[index]
+            .parents
+            .iter()
+            .fold(0, |row, p| row * self.card(*p) + assignment[*p])
+    }
+
+    /// P(· | fila de padres) del nodo `index`.
+    pub fn cpt_row(&self, index: usize, row: usize) -> &[f64] {
+        let card = self.card(index);
+        &self.nodes[index].cpt[row * card..(row + 1) * card]
+    }
+
+    /// Sustituye la CPT de `index` (misma disposición que `cpt`). Lo usa EM entre iteraciones.
+    pub fn set_cpt(&mut self, index: usize, cpt: Vec<f64>) {
+        debug_assert_eq!(cpt.len(), self.nodes[index].cpt.len());
+        self.nodes[index].cpt = cpt;
+    }
+
+    /// Traduce evidencia por id de suma_core a un vector indexado por nodo.
+    /// Devuelve `None` si algún nodo o estado no existe en la red.
+    pub fn evidence_vector(&self, evidence: &HashMap<usize, State>) -> Option<Vec<Option<usize>>> {
+        let mut observed = vec![None; self.nodes.len()];
+        for (id, state) in evidence {
+            let index = self.index_of(*id)?;
+            observed[index] = Some(self.state_index(index, state)?);
+        }
+        Some(observed)
+    }
+
+    /// Factor P(X | padres(X)) del nodo con índice `index`.
+    pub fn cpt_factor(&self, index: usize) -> Factor {
+        let node = &self.nodes[index];
+        let mut vars: Vec<usize> = node.parents.clone();
+        vars.push(index);
+        vars.sort_unstable();
+        let cards: Vec<usize> = vars.iter().map(|v| self.card(*v)).collect();
+
+        let own_pos = vars.iter().position(|v| *v == index).unwrap_or(0);
+        let parent_pos: Vec<usize> = node
+            .parents
+            .iter()
+            .map(|p| vars.iter().position(|v| v == p).unwrap_or(0))
+            .collect();
+        let card = node.states.len();
+
+        Factor::from_fn(vars, cards, |assignment| {
+            let mut row = 0;
+            for (p, pos) in node.parents.iter().zip(&parent_pos) {
+                row = row * self.card(*p) + assignment[*pos];
+            }
+            node.cpt[row * card + assignment[own_pos]]
+        })
+    }
+
+    /// Convierte una tabla indexada por estado en el mapa que devuelve suma_core.
+    pub fn to_distribution(&self, index: usize, values: &[f64]) -> HashMap<State, f64> {
+        self.nodes[index]
+            .states
+            .iter()
+            .cloned()
+            .zip(values.iter().copied())
+            .collect()
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use suma_core::core::probability::bayes::BN_base::State;
+
+use super::factor::Factor;
+use super::tables::NetworkTables;
+
+/// Inferencia exacta por eliminación de variables.
+///
+/// Misma firma que `likelihood_weighting_sampling` (ids de suma_core) para que
+/// ambos motores sean intercambiables. Si la evidencia es imposible o menciona
+/// un estado no declarado, devuelve una distribución vacía, igual que el muestreo.
+pub(crate) fn variable_elimination(
+    tables: &NetworkTables,
+    evidence: &HashMap<usize, State>,
+    query: usize,
+) -> Result<HashMap<State, f64>, String> {
+    let query_index = tables
+        .index_of(query)
+        .ok_or_else(|| format!("Query node {} not found", query))?;
+
+    let Some(observed) = tables.evidence_vector(evidence) else {
+        return Ok(HashMap::new());
+    };
+
+    let joint = eliminate_all_but(tables, &observed, &[query_index]);
+    if joint.total() <= 0.0 {
+        return Ok(HashMap::new());
+    }
+
+    let posterior = joint.normalized();
+    Ok(tables.to_distribution(query_index, &posterior.values))
+}
+
+/// Multiplica todas las CPTs (con la evidencia aplicada) y suma todas las
+/// variables que no estén en `keep`. El factor resultante no está normalizado:
+/// su suma total es P(evidencia).
+pub(crate) fn eliminate_all_but(tables: &NetworkTables, observed: &[Option<usize>], keep: &[usize]) -> Factor {
+    let mut factors = evidence_factors(tables, observed);
+
+    let scopes: Vec<Vec<usize>> = factors.iter().map(|f| f.vars.clone()).collect();
+    for var in elimination_order(tables, scopes, keep) {
+        let (related, rest): (Vec<Factor>, Vec<Factor>) =
+            factors.into_iter().partition(|f| f.position(var).is_some());
+        factors = rest;
+
+        let product = related.iter().fold(Factor::unit(), |acc, f| acc.product(f));
+        factors.push(product.sum_out(var));
+    }
+
+    factors.iter().fold(Factor::unit(), |acc, f| acc.product(f))
+}
+
+/// Una CPT por nodo, con las entradas incompatibles con la evidencia anuladas.
+pub(crate) fn evidence_factors(tables: &NetworkTables, observed: &[Option<usize>]) -> Vec<Factor> {
+    (0..tables.len())
+        .map(|i| {
+            let mut factor = tables.cpt_factor(i);
+            for var in factor.vars.clone() {
+                if let Some(state) = observed[var] {
+                    factor.observe(var, state);
+                }
+            }
+            factor
+        })
+        .collect()
+}
+
+/// Orden de eliminación de todas las variables fuera de `keep` según la heurística
+/// min-weight: en cada paso, la variable cuyo factor intermedio sea más pequeño.
+pub(crate) fn elimination_order(tables: &NetworkTables, mut scopes: Vec<Vec<usize>>, keep: &[usize]) -> Vec<usize> {
+    let mut pending: Vec<usize> = (0..tables.len()).filter(|v| !keep.contains(v)).collect();
+    let mut order = Vec::with_capacity(pending.len());
+
+    while !pending.is_empty() {
+        let mut best = (0, usize::MAX, Vec::new());
+        for (slot, &var) in pending.iter().enumerate() {
+            let mut scope: Vec<usize> = scopes
+                .iter()
+                .filter(|s| s.contains(&var))
+                .flat_map(|s| s.iter().copied())
+                .collect();
+            scope.sort_unstable();
+            scope.dedup();
+
+            let weight: usize = scope.iter().map(|v| tables.card(*v)).product();
+            if weight < best.1 {
+                best = (slot, weight, scope);
+            }
+        }
+
+        let (slot, _, mut merged) = best;
+        let var = pending.swap_remove(slot);
+        scopes.retain(|s| !s.contains(&var));
+        merged.retain(|v| *v != var);
+        scopes.push(merged);
+        order.push(var);
+    }
+
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::build_network_internal;
+    use crate::inference::InferenceOptions;
+    use crate::inference::sampling::likelihood_weighting;
+    use suma_core::core::probability::bayes::BayesianNetwork;
+
+    fn setup() -> (BayesianNetwork, NetworkTables) {
+        let bn = build_network_internal().expect("Failed to build Bayesian Network");
+        let tables = NetworkTables::from_network(&bn).expect("Failed to index network");
+        (bn, tables)
+    }
+
+    fn prob(distribution: &HashMap<State, f64>, state: &str) -> f64 {
+        distribution.get(&State::Value(state.to_string())).copied().unwrap_or(0.0)
+    }
+
+    #[test]
+    fn test_exact_prior_root() {
+        let (bn, tables) = setup();
+        let target = bn.get_id_from_name("EstadoOperativo").unwrap();
+
+        let distribution = variable_elimination(&tables, &HashMap::new(), target).unwrap();
+
+        assert!((prob(&distribution, "Normal") - 0.95).abs() < 1e-12);
+        assert!((prob(&distribution, "FallaMecanica") - 0.03).abs() < 1e-12);
+        assert!((prob(&distribution, "Fuga") - 0.02).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_exact_single_evidence_by_hand() {
+        let (bn, tables) = setup();
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("pH_sensor").unwrap(), State::Value("neutro".to_string()));
+        let target = bn.get_id_from_name("EstadoMicrobiano").unwrap();
+
+        let distribution = variable_elimination(&tables, &evidence, target).unwrap();
+
+        // P(neutro | Bueno) = 0.1*0.05 + 0.85*0.9 + 0.05*0.05 = 0.7725
+        // P(neutro | Degradado) = 0.6*0.05 + 0.3*0.9 + 0.1*0.05 = 0.305
+        let bueno = 0.85 * 0.7725;
+        let degradado = 0.15 * 0.305;
+        let expected = bueno / (bueno + degradado);
+        assert!((prob(&distribution, "Bueno") - expected).abs() < 1e-12, "{:?}", distribution);
+    }
+
+    #[test]
+    fn test_exact_matches_likelihood_weighting() {
+        let (bn, tables) = setup();
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("Gas_sensor").unwrap(), State::Value("bajo".to_string()));
+        evidence.insert(bn.get_id_from_name("Flow_sensor").unwrap(), State::Value("alto".to_string()));
+        // Semilla fija: el muestreo es reproducible y el test no falla al azar
+        let options = InferenceOptions { samples: 100_000, seed: Some(11), ..InferenceOptions::default() };
+
+        for target_name in ["EstadoMicrobiano", "EstadoOperativo", "ProduccionGasReal"] {
+            let target = bn.get_id_from_name(target_name).unwrap();
+            let exact = variable_elimination(&tables, &evidence, target).unwrap();
+            let sampled = likelihood_weighting(&tables, &evidence, target, &options).unwrap();
+
+            for (state, p) in &exact {
+                let q = sampled.get(state).copied().unwrap_or(0.0);
+                assert!((p - q).abs() < 0.03, "{} = {:?}: exacto {} vs muestreo {}", target_name, state, p, q);
+            }
+        }
+    }
+
+    #[test]
+    fn test_exact_unknown_state_is_empty() {
+        let (bn, tables) = setup();
+        let mut evidence = HashMap::new();
+        evidence.insert(bn.get_id_from_name("T_sensor").unwrap(), State::Value("Normal".to_string()));
+        let target = bn.get_id_from_name("EstadoMicrobiano").unwrap();
+
+        let distribution = variable_elimination(&tables, &evidence, target).unwrap();
+        assert!(distribution.is_empty());
+    }
+}
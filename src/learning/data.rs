@@ -0,0 +1,143 @@
+use serde_json::Value;
+
+// Tabla de casos: una columna por nodo y una fila por caso. Las celdas vacías, `?`,
+// `NA` (CSV) o `null` (JSON) son valores faltantes.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dataset {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Option<String>>>,
+}
+
+const MISSING: [&str; 3] = ["", "?", "NA"];
+
+impl Dataset {
+    /// CSV con cabecera y separador `,`. Los campos pueden ir entre comillas dobles
+    /// (`""` dentro de un campo es una comilla).
+    pub fn from_csv(text: &str) -> Result<Dataset, String> {
+        let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+        let (_, header) = lines.next().ok_or_else(|| "CSV: missing header".to_string())?;
+        let columns: Vec<String> = csv_fields(header)?.into_iter().map(|c| c.trim().to_string()).collect();
+
+        let mut rows = Vec::new();
+        for (number, line) in lines {
+            let fields = csv_fields(line).map_err(|e| format!("{} (line {})", e, number + 1))?;
+            if fields.len() != columns.len() {
+                return Err(format!("CSV: line {} has {} fields, expected {}", number + 1, fields.len(), columns.len()));
+            }
+            rows.push(fields.into_iter()
+                .map(|field| {
+                    let field = field.trim();
+                    (!MISSING.contains(&field)).then(|| field.to_string())
+                })
+                .collect());
+        }
+        Dataset::new(columns, rows)
+    }
+
+    /// Array de objetos `{ nodo: estado }`. Los números y booleanos se leen como texto;
+    /// una clave ausente o `null` es un valor faltante.
+    pub fn from_json(text: &str) -> Result<Dataset, String> {
+        let value: Value = serde_json::from_str(text).map_err(|e| format!("Invalid data JSON: {}", e))?;
+        let cases = value.as_array().ok_or_else(|| "Data JSON must be an array of cases".to_string())?;
+
+        let mut columns: Vec<String> = Vec::new();
+        for case in cases {
+            let case = case.as_object().ok_or_else(|| "Every case must be an object".to_string())?;
+            for key in case.keys() {
+                if !columns.contains(key) {
+                    columns.push(key.clone());
+                }
+            }
+        }
+
+        let rows = cases.iter()
+            .enumerate()
+            .map(|(number, case)| {
+                columns.iter()
+                    .map(|column| match case.get(column) {
+                        None | Some(Value::Null) => Ok(None),
+                        Some(Value::String(s)) => Ok(Some(s.clone())),
+                        Some(Value::Number(n)) => Ok(Some(n.to_string())),
+                        Some(Value::Bool(b)) => Ok(Some(b.to_string())),
+                        Some(other) => Err(format!("Case {}: '{}' must be a state name, found {}", number + 1, column, other)),
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, String>>()?;
+        Dataset::new(columns, rows)
+    }
+
+    fn new(columns: Vec<String>, rows: Vec<Vec<Option<String>>>) -> Result<Dataset, String> {
+        for (i, column) in columns.iter().enumerate() {
+            if columns[..i].contains(column) {
+                return Err(format!("Column '{}' appears twice", column));
+            }
+        }
+        Ok(Dataset { columns, rows })
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c == name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+}
+
+fn csv_fields(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("CSV: unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_with_quotes_and_missing_values() {
+        let csv = "T_sensor, EstadoMicrobiano ,Nota\nalta,Degradado,\"con, coma\"\n\nnormal,?,\"dice \"\"ok\"\"\"\r\n";
+        let data = Dataset::from_csv(csv).unwrap();
+        assert_eq!(data.columns, vec!["T_sensor", "EstadoMicrobiano", "Nota"]);
+        assert_eq!(data.len(), 2);
+        assert_eq!(data.rows[0][2].as_deref(), Some("con, coma"));
+        assert_eq!(data.rows[1][1], None);
+        assert_eq!(data.rows[1][2].as_deref(), Some("dice \"ok\""));
+
+        assert!(Dataset::from_csv("a,b\n1,2,3").is_err());
+        assert!(Dataset::from_csv("a,a\n1,2").is_err());
+    }
+
+    #[test]
+    fn test_json_cases() {
+        let json = r#"[ { "A": "si", "B": true }, { "A": null, "C": 3 } ]"#;
+        let data = Dataset::from_json(json).unwrap();
+        assert_eq!(data.columns, vec!["A", "B", "C"]);
+        assert_eq!(data.rows[0], vec![Some("si".to_string()), Some("true".to_string()), None]);
+        assert_eq!(data.rows[1], vec![None, None, Some("3".to_string())]);
+        assert!(Dataset::from_json(r#"{ "A": "si" }"#).is_err());
+    }
+}
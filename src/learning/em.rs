@@ -0,0 +1,251 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use super::parameters::{case_states, empty_counts, estimate};
+use super::{Dataset, Prior};
+use crate::inference::factor::increment;
+use crate::inference::sampling::SampleRng;
+use crate::inference::tables::NetworkTables;
+use crate::inference::variable_elimination::eliminate_all_but;
+use crate::model::ModelSpec;
+
+// Expectation-maximization para datos incompletos. En cada iteración, el paso E
+// reparte cada caso entre las combinaciones compatibles de cada familia (nodo y
+// padres) según la posterior con las CPTs actuales; el paso M reestima las CPTs con
+// esos conteos esperados, igual que `learn_parameters` con conteos enteros.
+
+/// Parámetros de EM. Todos tienen valor por defecto.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct EmOptions {
+    /// Máximo de iteraciones (pasos M).
+    pub max_iterations: usize,
+    /// Se considera convergido cuando la log-verosimilitud cambia menos que esto entre
+    /// iteraciones (en valor absoluto: con prior puede bajar un poco).
+    pub tolerance: f64,
+    pub initialization: Initialization,
+    /// Con un prior, EM maximiza la posterior en lugar de la verosimilitud.
+    pub prior: Prior,
+}
+
+impl Default for EmOptions {
+    fn default() -> Self {
+        EmOptions { max_iterations: 100, tolerance: 1e-6, initialization: Initialization::Current, prior: Prior::None }
+    }
+}
+
+impl EmOptions {
+    /// Rechaza tolerancias negativas, ruidos fuera de [0, 1] y priors inválidos.
+    pub fn check(&self) -> Result<(), String> {
+        self.prior.check()?;
+        if self.tolerance.is_nan() || self.tolerance < 0.0 {
+            return Err(format!("EM tolerance must be non-negative, got {}", self.tolerance));
+        }
+        match self.initialization {
+            Initialization::Perturbed { noise, .. } if !(0.0..=1.0).contains(&noise) => {
+                Err(format!("EM noise must be between 0 and 1, got {}", noise))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// CPTs de partida.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Initialization {
+    /// Las CPTs del modelo tal cual.
+    #[default]
+    Current,
+    /// Todas las filas uniformes. Con nodos ocultos es un punto simétrico del que EM
+    /// no sale: conviene combinarlo con datos de esos nodos o usar `Perturbed`.
+    Uniform,
+    /// `(1 - noise)` × CPT actual + `noise` × fila aleatoria, reproducible con `seed`.
+    Perturbed { noise: f64, seed: u64 },
+}
+
+/// Evolución de un ajuste por EM.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EmTrace {
+    /// Log-verosimilitud de los datos con las CPTs de partida y tras cada iteración.
+    pub log_likelihood: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+impl ModelSpec {
+    /// CPTs estimadas por EM con casos a los que les faltan valores (columnas ausentes
+    /// o celdas vacías). Se reestiman todos los nodos; los que no tienen datos ni
+    /// descendientes observados conservan su CPT.
+    pub fn learn_em(&self, data: &Dataset, options: &EmOptions) -> Result<(ModelSpec, EmTrace), String> {
+        options.check()?;
+        let mut tables = NetworkTables::from_network(&self.build_network()?)?;
+        initialize(&mut tables, &options.initialization);
+
+        // Los casos con el mismo patrón de observaciones se procesan una sola vez
+        let mut patterns: BTreeMap<Vec<Option<usize>>, (usize, f64)> = BTreeMap::new();
+        for (number, case) in case_states(&tables, data)?.into_iter().enumerate() {
+            patterns.entry(case).or_insert((number, 0.0)).1 += 1.0;
+        }
+
+        let mut trace = EmTrace { log_likelihood: Vec::new(), iterations: 0, converged: false };
+        loop {
+            let (log_likelihood, counts) = expected_counts(&tables, &patterns)?;
+            let previous = trace.log_likelihood.last().copied();
+            trace.log_likelihood.push(log_likelihood);
+            if previous.is_some_and(|previous| (log_likelihood - previous).abs() < options.tolerance) {
+                trace.converged = true;
+                break;
+            }
+            if trace.iterations == options.max_iterations {
+                break;
+            }
+
+            let cpts: Vec<Vec<f64>> = (0..tables.len())
+                .map(|index| estimate(&tables, index, &counts[index], &options.prior))
+                .collect();
+            for (index, cpt) in cpts.into_iter().enumerate() {
+                tables.set_cpt(index, cpt);
+            }
+            trace.iterations += 1;
+        }
+
+        let cpts: Vec<Vec<f64>> = tables.nodes().iter().map(|node| node.cpt.clone()).collect();
+        Ok((self.with_cpts(&tables, &cpts)?, trace))
+    }
+}
+
+fn initialize(tables: &mut NetworkTables, initialization: &Initialization) {
+    match *initialization {
+        Initialization::Current => {}
+        Initialization::Uniform => {
+            for index in 0..tables.len() {
+                let card = tables.card(index);
+                let size = tables.nodes()[index].cpt.len();
+                tables.set_cpt(index, vec![1.0 / card as f64; size]);
+            }
+        }
+        Initialization::Perturbed { noise, seed } => {
+            let mut rng = SampleRng::new(seed);
+            for index in 0..tables.len() {
+                let card = tables.card(index);
+                let mut cpt = Vec::with_capacity(tables.nodes()[index].cpt.len());
+                for current in tables.nodes()[index].cpt.chunks(card) {
+                    // Una fila indefinida (todo ceros) parte de la uniforme
+                    let total: f64 = current.iter().sum();
+                    let random: Vec<f64> = (0..card).map(|_| rng.next_f64() + 1e-3).collect();
+                    let random_total: f64 = random.iter().sum();
+                    cpt.extend(current.iter().zip(&random).map(|(p, r)| {
+                        let p = if total > 0.0 { p / total } else { 1.0 / card as f64 };
+                        (1.0 - noise) * p + noise * r / random_total
+                    }));
+                }
+                tables.set_cpt(index, cpt);
+            }
+        }
+    }
+}
+
+// Paso E: log-verosimilitud de los datos y conteos esperados de cada familia.
+fn expected_counts(
+    tables: &NetworkTables,
+    patterns: &BTreeMap<Vec<Option<usize>>, (usize, f64)>,
+) -> Result<(f64, Vec<Vec<f64>>), String> {
+    let mut counts = empty_counts(tables);
+    let mut log_likelihood = 0.0;
+    for (observed, (number, weight)) in patterns {
+        let evidence = eliminate_all_but(tables, observed, &[]).total();
+        if evidence <= 0.0 {
+            return Err(format!("Case {} has zero probability under the current CPTs", number + 1));
+        }
+        log_likelihood += weight * evidence.ln();
+
+        add_expected_counts(tables, observed, evidence, *weight, &mut counts);
+    }
+    Ok((log_likelihood, counts))
+}
+
+/// Suma a `counts` el caso `observed` (con probabilidad `evidence`) repartido entre las
+/// combinaciones de cada familia según su posterior, multiplicado por `weight`.
+pub(crate) fn add_expected_counts(
+    tables: &NetworkTables,
+    observed: &[Option<usize>],
+    evidence: f64,
+    weight: f64,
+    counts: &mut [Vec<f64>],
+) {
+    for index in 0..tables.len() {
+        let mut family = tables.nodes()[index].parents.clone();
+        family.push(index);
+        let factor = eliminate_all_but(tables, observed, &family);
+
+        let card = tables.card(index);
+        let mut assignment = vec![0; tables.len()];
+        let mut states = vec![0; factor.vars.len()];
+        for value in &factor.values {
+            if *value > 0.0 {
+                for (var, state) in factor.vars.iter().zip(&states) {
+                    assignment[*var] = *state;
+                }
+                let row = tables.parent_row(index, &assignment);
+                counts[index][row * card + assignment[index]] += weight * value / evidence;
+            }
+            increment(&mut states, &factor.cards);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning::fuga_spec;
+    use crate::build::default_model;
+
+    fn spec() -> ModelSpec {
+        fuga_spec(0.5, 0.5, 0.5)
+    }
+
+    fn non_decreasing(trace: &EmTrace) -> bool {
+        trace.log_likelihood.windows(2).all(|w| w[1] >= w[0] - 1e-9)
+    }
+
+    #[test]
+    fn test_hidden_parent_fits_observed_marginal() {
+        // Fuga nunca se observa: EM ajusta P(Presion) a la frecuencia empírica
+        let data = Dataset::from_csv("Presion\nbaja\nbaja\nbaja\nalta\n").unwrap();
+        let (learned, trace) = spec().learn_em(&data, &EmOptions::default()).unwrap();
+        assert!(trace.converged && non_decreasing(&trace), "{:?}", trace);
+        assert_eq!(trace.log_likelihood.len(), trace.iterations + 1);
+
+        let presion = learned.nodes.iter().find(|n| n.name == "Presion").unwrap();
+        for row in &presion.cpt {
+            assert!((row.probabilities["baja"] - 0.75).abs() < 1e-9);
+        }
+        let expected = 3.0 * 0.75_f64.ln() + 0.25_f64.ln();
+        assert!((trace.log_likelihood.last().unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sensor_logs_update_hidden_nodes() {
+        let spec = default_model().unwrap();
+        let mut csv = String::from("T_sensor,pH_sensor,Flow_sensor,Gas_sensor,Presion_sensor\n");
+        for _ in 0..10 {
+            csv.push_str("alta,acido,normal,bajo,normal\nnormal,neutro,normal,normal,normal\nnormal,neutro,bajo,bajo,\n");
+        }
+        let data = Dataset::from_csv(&csv).unwrap();
+
+        let options = EmOptions { max_iterations: 20, ..EmOptions::default() };
+        let (learned, trace) = spec.learn_em(&data, &options).unwrap();
+        assert!(non_decreasing(&trace), "{:?}", trace);
+        assert!(trace.iterations <= 20 && trace.log_likelihood[trace.iterations] > trace.log_likelihood[0]);
+        assert_ne!(learned.nodes[0].cpt, spec.nodes[0].cpt);
+        assert!(learned.validate().valid);
+
+        let perturbed = EmOptions { initialization: Initialization::Perturbed { noise: 0.2, seed: 7 }, max_iterations: 3, ..options };
+        assert_eq!(spec.learn_em(&data, &perturbed).unwrap(), spec.learn_em(&data, &perturbed).unwrap());
+        let bad = EmOptions { initialization: Initialization::Perturbed { noise: 2.0, seed: 7 }, ..options };
+        assert!(spec.learn_em(&data, &bad).is_err());
+        let negative = EmOptions { prior: Prior::Uniform { alpha: -1.0 }, ..options };
+        assert!(spec.learn_em(&data, &negative).is_err());
+    }
+}
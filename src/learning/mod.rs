@@ -0,0 +1,61 @@
+// Aprendizaje a partir de registros históricos: tablas de casos (CSV o JSON) y
+// estimación de las CPTs de un `ModelSpec` existente, con datos completos o, con EM,
+// con nodos que nunca se observan, o caso a caso sobre el modelo desplegado; y
+// búsqueda de la estructura que mejor explica los datos, por puntuación o con tests
+// de independencia (PC).
+
+pub mod data;
+pub mod em;
+pub mod online;
+pub mod parameters;
+pub mod pc;
+pub mod structure;
+
+pub use data::Dataset;
+pub use em::{EmOptions, EmTrace, Initialization};
+pub use online::Adaptation;
+pub use parameters::Prior;
+pub use pc::{IndependenceCheck, IndependenceTest, PcOptions, PcResult};
+pub use structure::{Edge, EdgeDiff, Score, Search, StartGraph, StructureOptions, StructureResult};
+
+/// Red de dos nodos Fuga -> Presion con P(Fuga = si) = `fuga` y P(Presion = baja | Fuga)
+/// = `baja_si` / `baja_no` (para los tests de aprendizaje).
+#[cfg(test)]
+pub(crate) fn fuga_spec(fuga: f64, baja_si: f64, baja_no: f64) -> crate::model::ModelSpec {
+    crate::model::ModelSpec::from_yaml(&format!(r#"
+name: fuga
+nodes:
+  - name: Fuga
+    states: [si, no]
+    cpt: [ {{ given: [], probabilities: {{ si: {}, no: {} }} }} ]
+  - name: Presion
+    states: [baja, alta]
+    parents: [Fuga]
+    cpt:
+      - {{ given: [si], probabilities: {{ baja: {}, alta: {} }} }}
+      - {{ given: [no], probabilities: {{ baja: {}, alta: {} }} }}
+"#, fuga, 1.0 - fuga, baja_si, 1.0 - baja_si, baja_no, 1.0 - baja_no)).unwrap()
+}
+
+/// `cases` casos completos muestreados de `spec` (para los tests de aprendizaje).
+#[cfg(test)]
+pub(crate) fn sample_dataset(spec: &crate::model::ModelSpec, cases: usize, seed: u64) -> Dataset {
+    use crate::inference::sampling::SampleRng;
+    use crate::inference::tables::NetworkTables;
+
+    let tables = NetworkTables::from_network(&spec.build_network().unwrap()).unwrap();
+    let order = tables.topological_order();
+    let mut rng = SampleRng::new(seed);
+    let rows = (0..cases)
+        .map(|_| {
+            let mut sample = vec![0; tables.len()];
+            for &index in &order {
+                sample[index] = rng.categorical(tables.cpt_row(index, tables.parent_row(index, &sample)));
+            }
+            (0..tables.len())
+                .map(|index| Some(crate::state_to_string(tables.nodes()[index].states[sample[index]].clone())))
+                .collect()
+        })
+        .collect();
+    Dataset { columns: tables.nodes().iter().map(|node| node.name.clone()).collect(), rows }
+}
@@ -0,0 +1,106 @@
+use super::em::add_expected_counts;
+use super::parameters::empty_counts;
+use crate::inference::tables::NetworkTables;
+
+// Actualización secuencial de las CPTs: cada fila guarda conteos de experiencia
+// (parámetros de una Dirichlet) y cada caso confirmado se suma a ellos. Con datos
+// incompletos el caso se reparte según la posterior de cada familia, como en EM.
+
+/// Experiencia por defecto: las CPTs escritas a mano pesan como 10 casos por fila.
+pub const DEFAULT_EXPERIENCE: f64 = 10.0;
+
+/// Estado de la adaptación en línea de un modelo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adaptation {
+    /// Factor de olvido en (0, 1]: antes de sumar un caso, la experiencia de cada fila
+    /// que lo recibe se multiplica por `fading` (elevado a la parte del caso que le toca).
+    /// Con 1 no se olvida nada; con 0.99 la memoria efectiva es de unos 100 casos.
+    pub fading: f64,
+    /// Conteos con la disposición de las CPTs de `NetworkTables`.
+    experience: Vec<Vec<f64>>,
+}
+
+impl Adaptation {
+    /// Experiencia inicial de `experience` casos por fila, repartidos según la CPT actual.
+    pub(crate) fn new(tables: &NetworkTables, experience: f64, fading: f64) -> Result<Adaptation, String> {
+        if experience.is_nan() || experience < 0.0 {
+            return Err(format!("Experience must be non-negative, got {}", experience));
+        }
+        if !(fading > 0.0 && fading <= 1.0) {
+            return Err(format!("Fading factor must be in (0, 1], got {}", fading));
+        }
+        let experience = tables.nodes().iter()
+            .map(|node| node.cpt.iter().map(|p| p * experience).collect())
+            .collect();
+        Ok(Adaptation { fading, experience })
+    }
+
+    /// Suma el caso `observed` (probabilidad `evidence` bajo las CPTs de `tables`) con
+    /// peso `weight` y devuelve las CPTs nuevas. Las filas que no reciben nada del caso
+    /// quedan exactamente como estaban.
+    pub(crate) fn observe(&mut self, tables: &NetworkTables, observed: &[Option<usize>], evidence: f64, weight: f64) -> Vec<Vec<f64>> {
+        let mut case = empty_counts(tables);
+        add_expected_counts(tables, observed, evidence, weight, &mut case);
+
+        (0..tables.len())
+            .map(|index| {
+                let card = tables.card(index);
+                let mut cpt = tables.nodes()[index].cpt.clone();
+                let rows = self.experience[index].chunks_mut(card).zip(case[index].chunks(card));
+                for (row, (experience, counts)) in rows.enumerate() {
+                    let share: f64 = counts.iter().sum::<f64>() / weight;
+                    if share <= 0.0 {
+                        continue;
+                    }
+                    let fade = self.fading.powf(weight * share);
+                    for (n, c) in experience.iter_mut().zip(counts) {
+                        *n = *n * fade + c;
+                    }
+                    let total: f64 = experience.iter().sum();
+                    if total > 0.0 {
+                        for (p, n) in cpt[row * card..(row + 1) * card].iter_mut().zip(experience.iter()) {
+                            *p = n / total;
+                        }
+                    }
+                }
+                cpt
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use suma_core::core::probability::bayes::BN_base::State;
+    use crate::learning::fuga_spec;
+
+    #[test]
+    fn test_sequential_update_and_fading() {
+        let spec = fuga_spec(0.2, 0.9, 0.1);
+        let tables = NetworkTables::from_network(&spec.build_network().unwrap()).unwrap();
+        let fuga = tables.nodes().iter().position(|n| n.name == "Fuga").unwrap();
+        let presion = tables.nodes().iter().position(|n| n.name == "Presion").unwrap();
+        let state = |index: usize, name: &str| tables.state_index(index, &State::from_str(name)).unwrap();
+        let (si, alta) = (state(fuga, "si"), state(presion, "alta"));
+        let mut observed = vec![None; 2];
+        observed[fuga] = Some(si);
+        observed[presion] = Some(alta);
+
+        // 10 casos de experiencia: (2 + 1) / 11 para Fuga = si
+        let mut adaptation = Adaptation::new(&tables, 10.0, 1.0).unwrap();
+        let evidence = 0.2 * 0.1;
+        let cpts = adaptation.observe(&tables, &observed, evidence, 1.0);
+        assert!((cpts[fuga][si] - 3.0 / 11.0).abs() < 1e-12);
+        assert!((cpts[presion][si * 2 + alta] - 2.0 / 11.0).abs() < 1e-12);
+        let other = 1 - si;
+        assert_eq!(cpts[presion][other * 2..other * 2 + 2], tables.nodes()[presion].cpt[other * 2..other * 2 + 2]);
+        assert!((adaptation.experience[presion][si * 2..si * 2 + 2].iter().sum::<f64>() - 11.0).abs() < 1e-12);
+
+        // Con olvido 0.5 la experiencia previa pasa a valer 5 casos: (1 + 1) / 6
+        let mut fading = Adaptation::new(&tables, 10.0, 0.5).unwrap();
+        let cpts = fading.observe(&tables, &observed, evidence, 1.0);
+        assert!((cpts[fuga][si] - 2.0 / 6.0).abs() < 1e-12);
+        assert!(Adaptation::new(&tables, 10.0, 0.0).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+use serde::Deserialize;
+use suma_core::core::probability::bayes::BN_base::State;
+
+use super::Dataset;
+use crate::evidence::UnknownState;
+use crate::formats::parent_configurations;
+use crate::inference::tables::NetworkTables;
+use crate::model::{CptRow, ModelSpec};
+use crate::state_to_string;
+
+// Estimación de CPTs por conteo. Los conteos (enteros con datos completos, esperados
+// en EM) se guardan con la disposición de `NetworkTables`: una fila por combinación
+// de padres (ordenados por id) y un valor por estado.
+
+/// Distribución a priori de cada fila de la CPT (Dirichlet).
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Prior {
+    /// Máxima verosimilitud: solo los conteos. Las filas sin datos conservan la CPT actual.
+    #[default]
+    None,
+    /// `alpha` pseudo-conteos en cada estado (`alpha = 1` es la corrección de Laplace).
+    Uniform { alpha: f64 },
+    /// La CPT actual como media de la Dirichlet, con el peso de `weight` casos por fila.
+    Model { weight: f64 },
+}
+
+impl Prior {
+    /// Rechaza pseudo-conteos negativos o no finitos, que darían filas fuera de [0, 1].
+    pub fn check(&self) -> Result<(), String> {
+        match *self {
+            Prior::Uniform { alpha } if !(alpha.is_finite() && alpha >= 0.0) => {
+                Err(format!("Prior alpha must be a finite number >= 0, got {}", alpha))
+            }
+            Prior::Model { weight } if !(weight.is_finite() && weight >= 0.0) => {
+                Err(format!("Prior weight must be a finite number >= 0, got {}", weight))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl ModelSpec {
+    /// CPTs estimadas con los casos completos de `data`. Se reestiman los nodos que
+    /// tienen columna propia y columnas para todos sus padres; el resto conserva su
+    /// CPT. Las columnas que no son nodos se ignoran.
+    pub fn learn_parameters(&self, data: &Dataset, prior: &Prior) -> Result<ModelSpec, String> {
+        prior.check()?;
+        let tables = NetworkTables::from_network(&self.build_network()?)?;
+        let cases = case_states(&tables, data)?;
+
+        let learned: Vec<bool> = (0..tables.len())
+            .map(|index| {
+                let node = &tables.nodes()[index];
+                std::iter::once(index).chain(node.parents.iter().copied())
+                    .all(|i| data.column(&tables.nodes()[i].name).is_some())
+            })
+            .collect();
+
+        let mut counts = empty_counts(&tables);
+        for (number, case) in cases.iter().enumerate() {
+            let mut assignment = vec![0; tables.len()];
+            for (index, state) in case.iter().enumerate() {
+                assignment[index] = state.unwrap_or(0);
+            }
+            for index in (0..tables.len()).filter(|i| learned[*i]) {
+                let node = &tables.nodes()[index];
+                let family = std::iter::once(&index).chain(&node.parents);
+                if let Some(missing) = family.clone().find(|i| case[**i].is_none()) {
+                    return Err(format!(
+                        "Case {} has no value for '{}' (incomplete data needs EM)",
+                        number + 1,
+                        tables.nodes()[*missing].name
+                    ));
+                }
+                let row = tables.parent_row(index, &assignment);
+                counts[index][row * tables.card(index) + assignment[index]] += 1.0;
+            }
+        }
+
+        let cpts: Vec<Vec<f64>> = (0..tables.len())
+            .map(|index| {
+                if learned[index] {
+                    estimate(&tables, index, &counts[index], prior)
+                } else {
+                    tables.nodes()[index].cpt.clone()
+                }
+            })
+            .collect();
+        self.with_cpts(&tables, &cpts)
+    }
+
+    /// Copia del modelo con las CPTs dadas en la disposición de `tables` (construidas
+    /// a partir de este mismo modelo). Conserva el orden de nodos, padres y metadatos.
+    pub(crate) fn with_cpts(&self, tables: &NetworkTables, cpts: &[Vec<f64>]) -> Result<ModelSpec, String> {
+        let index_of = |name: &str| tables.nodes().iter().position(|n| n.name == name)
+            .ok_or_else(|| format!("Node '{}' is not in the network", name));
+
+        let mut spec = self.clone();
+        for node in &mut spec.nodes {
+            let index = index_of(&node.name)?;
+            let card = tables.card(index);
+            let parents: Vec<usize> = node.parents.iter().map(|p| index_of(p)).collect::<Result<_, _>>()?;
+
+            let mut cpt = Vec::new();
+            for given in parent_configurations(self, node)? {
+                let mut assignment = vec![0; tables.len()];
+                for (parent, state) in parents.iter().zip(&given) {
+                    assignment[*parent] = tables.nodes()[*parent].states.iter()
+                        .position(|s| &state_to_string(s.clone()) == state)
+                        .ok_or_else(|| format!("Node '{}': unknown state '{}'", tables.nodes()[*parent].name, state))?;
+                }
+                let row = tables.parent_row(index, &assignment);
+                let values = &cpts[index][row * card..(row + 1) * card];
+                if values.iter().any(|p| *p != 0.0) {
+                    cpt.push(CptRow { given, probabilities: node.states.iter().cloned().zip(values.iter().copied()).collect() });
+                }
+            }
+            node.cpt = cpt;
+        }
+        Ok(spec)
+    }
+}
+
+/// Estado de cada nodo en cada caso (`None` si falta la columna o el valor). Un estado
+/// no declarado es un error, con la misma sugerencia que la evidencia.
+pub(crate) fn case_states(tables: &NetworkTables, data: &Dataset) -> Result<Vec<Vec<Option<usize>>>, String> {
+    let columns: Vec<Option<usize>> = tables.nodes().iter().map(|node| data.column(&node.name)).collect();
+    data.rows.iter()
+        .enumerate()
+        .map(|(number, row)| {
+            columns.iter()
+                .enumerate()
+                .map(|(index, column)| {
+                    let Some(value) = column.and_then(|c| row[c].as_deref()) else {
+                        return Ok(None);
+                    };
+                    tables.state_index(index, &State::from_str(value)).map(Some).ok_or_else(|| {
+                        let node = &tables.nodes()[index];
+                        let valid = node.states.iter().cloned().map(state_to_string).collect();
+                        format!("Case {}: {}", number + 1, UnknownState::new(&node.name, value, valid))
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Conteos a cero con la forma de las CPTs de `tables`.
+pub(crate) fn empty_counts(tables: &NetworkTables) -> Vec<Vec<f64>> {
+    tables.nodes().iter().map(|node| vec![0.0; node.cpt.len()]).collect()
+}
+
+/// Media posterior de cada fila de la CPT de `index` dados sus conteos y el prior.
+pub(crate) fn estimate(tables: &NetworkTables, index: usize, counts: &[f64], prior: &Prior) -> Vec<f64> {
+    let card = tables.card(index);
+    let mut cpt = Vec::with_capacity(counts.len());
+    for (row, row_counts) in counts.chunks(card).enumerate() {
+        let current = tables.cpt_row(index, row);
+        let current_total: f64 = current.iter().sum();
+        let pseudo: Vec<f64> = match prior {
+            Prior::None => vec![0.0; card],
+            Prior::Uniform { alpha } => vec![*alpha; card],
+            // Una fila actual indefinida (todo ceros) cuenta como uniforme
+            Prior::Model { weight } if current_total > 0.0 => current.iter().map(|p| weight * p / current_total).collect(),
+            Prior::Model { weight } => vec![weight / card as f64; card],
+        };
+        let total: f64 = row_counts.iter().zip(&pseudo).map(|(n, a)| n + a).sum();
+        if total > 0.0 {
+            cpt.extend(row_counts.iter().zip(&pseudo).map(|(n, a)| (n + a) / total));
+        } else {
+            cpt.extend_from_slice(current);
+        }
+    }
+    cpt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning::fuga_spec;
+    use crate::build::default_model;
+
+    fn spec() -> ModelSpec {
+        fuga_spec(0.5, 0.5, 0.5)
+    }
+
+    fn row(spec: &ModelSpec, node: &str, given: &[&str]) -> Vec<f64> {
+        let node = spec.nodes.iter().find(|n| n.name == node).unwrap();
+        let row = node.cpt.iter().find(|r| r.given == given).unwrap();
+        node.states.iter().map(|s| row.probabilities[s]).collect()
+    }
+
+    #[test]
+    fn test_maximum_likelihood_counts() {
+        // 3 casos con fuga (2 con presión baja) y 1 sin fuga
+        let data = Dataset::from_csv("Fuga,Presion,Hora\nsi,baja,1\nsi,baja,2\nsi,alta,3\nno,alta,4\n").unwrap();
+        let learned = spec().learn_parameters(&data, &Prior::None).unwrap();
+        assert_eq!(row(&learned, "Fuga", &[]), vec![0.75, 0.25]);
+        assert_eq!(row(&learned, "Presion", &["si"]), vec![2.0 / 3.0, 1.0 / 3.0]);
+        assert_eq!(row(&learned, "Presion", &["no"]), vec![0.0, 1.0]);
+        assert!(learned.build_network().is_ok());
+
+        let typo = Dataset::from_csv("Fuga,Presion\nSi,baja\n").unwrap();
+        let error = spec().learn_parameters(&typo, &Prior::None).unwrap_err();
+        assert!(error.contains("Case 1") && error.contains("did you mean 'si'"), "{}", error);
+        let incomplete = Dataset::from_csv("Fuga,Presion\nsi,\n").unwrap();
+        assert!(spec().learn_parameters(&incomplete, &Prior::None).is_err());
+    }
+
+    #[test]
+    fn test_dirichlet_priors() {
+        let data = Dataset::from_json(r#"[ { "Fuga": "si", "Presion": "baja" } ]"#).unwrap();
+
+        // Laplace: (1 + 1) / (1 + 2) para el estado visto
+        let laplace = spec().learn_parameters(&data, &Prior::Uniform { alpha: 1.0 }).unwrap();
+        assert_eq!(row(&laplace, "Presion", &["si"]), vec![2.0 / 3.0, 1.0 / 3.0]);
+        assert_eq!(row(&laplace, "Presion", &["no"]), vec![0.5, 0.5]);
+
+        // La CPT experta pesa como 3 casos: (1 + 3 × 0.5) / 4
+        let expert = spec().learn_parameters(&data, &Prior::Model { weight: 3.0 }).unwrap();
+        assert_eq!(row(&expert, "Presion", &["si"]), vec![0.625, 0.375]);
+
+        for prior in [Prior::Uniform { alpha: -1.0 }, Prior::Model { weight: f64::INFINITY }] {
+            assert!(spec().learn_parameters(&data, &prior).is_err(), "{:?}", prior);
+        }
+    }
+
+    #[test]
+    fn test_nodes_without_columns_keep_their_cpt() {
+        let spec = default_model().unwrap();
+        let data = Dataset::from_csv("EstadoMicrobiano,TemperaturaReal\nDegradado,Baja\nBueno,Normal\n").unwrap();
+        let learned = spec.learn_parameters(&data, &Prior::Model { weight: 10.0 }).unwrap();
+
+        assert_eq!(row(&learned, "T_sensor", &["Alta"]), row(&spec, "T_sensor", &["Alta"]));
+        assert_ne!(row(&learned, "EstadoMicrobiano", &[]), row(&spec, "EstadoMicrobiano", &[]));
+        assert_eq!(learned.nodes[0].metadata, spec.nodes[0].metadata);
+        assert!(learned.validate().valid);
+    }
+}
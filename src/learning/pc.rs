@@ -0,0 +1,373 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+
+use super::structure::{complete_columns, ln_gamma, Edge};
+use super::Dataset;
+use crate::inference::tables::NetworkTables;
+use crate::model::ModelSpec;
+
+// Algoritmo PC (variante estable): parte del grafo completo entre los nodos con datos
+// y quita cada arista cuyos extremos resultan independientes dado algún subconjunto
+// de vecinos, con conjuntos condicionantes cada vez mayores. Después orienta las
+// v-estructuras y propaga con las reglas de Meek. El resultado es un CPDAG: las
+// aristas que los datos no permiten orientar quedan sin dirección.
+
+/// Test de independencia condicional sobre tablas de contingencia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndependenceTest {
+    /// Chi-cuadrado de Pearson.
+    #[default]
+    ChiSquare,
+    /// Razón de verosimilitudes (G² = 2 Σ O ln(O / E)).
+    GTest,
+}
+
+/// Parámetros de PC. Todos tienen valor por defecto.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct PcOptions {
+    pub test: IndependenceTest,
+    /// Nivel de significación: con p-valor mayor se acepta la independencia.
+    pub alpha: f64,
+    /// Tamaño máximo del conjunto condicionante.
+    pub max_conditioning: Option<usize>,
+}
+
+impl Default for PcOptions {
+    fn default() -> Self {
+        PcOptions { test: IndependenceTest::ChiSquare, alpha: 0.05, max_conditioning: None }
+    }
+}
+
+impl PcOptions {
+    /// Rechaza niveles de significación fuera de (0, 1).
+    pub fn check(&self) -> Result<(), String> {
+        if !(self.alpha > 0.0 && self.alpha < 1.0) {
+            return Err(format!("Significance level must be in (0, 1), got {}", self.alpha));
+        }
+        Ok(())
+    }
+}
+
+/// Un test realizado: ¿`x` ⟂ `y` | `given`?
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct IndependenceCheck {
+    pub x: String,
+    pub y: String,
+    pub given: Vec<String>,
+    pub test: IndependenceTest,
+    pub statistic: f64,
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+    /// Si el test acepta la independencia (y por tanto quita la arista).
+    pub independent: bool,
+}
+
+/// Resultado de PC.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PcResult {
+    /// Nodos que intervienen (los que tienen columna), en el orden del modelo.
+    pub variables: Vec<String>,
+    /// Arcos que los datos orientan.
+    pub directed: Vec<Edge>,
+    /// Aristas sin orientación (`from`/`to` en el orden de `variables`).
+    pub undirected: Vec<Edge>,
+    /// Todos los tests, en el orden en que se hicieron.
+    pub tests: Vec<IndependenceCheck>,
+}
+
+impl ModelSpec {
+    /// CPDAG aprendido con el algoritmo PC a partir de los casos completos de `data`.
+    /// Solo intervienen los nodos con columna; los estados son los que declara el modelo.
+    pub fn learn_pc(&self, data: &Dataset, options: &PcOptions) -> Result<PcResult, String> {
+        options.check()?;
+        let tables = NetworkTables::from_network(&self.build_network()?)?;
+        let (variables, columns) = complete_columns(&tables, data, "the PC algorithm")?;
+        let names: Vec<String> = variables.iter().map(|i| tables.nodes()[*i].name.clone()).collect();
+        let cards: Vec<usize> = variables.iter().map(|i| tables.card(*i)).collect();
+        let n = variables.len();
+
+        let mut adjacent = vec![vec![true; n]; n];
+        for (i, row) in adjacent.iter_mut().enumerate() {
+            row[i] = false;
+        }
+        let mut separating: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        let mut tests = Vec::new();
+
+        let mut size = 0;
+        while options.max_conditioning.is_none_or(|max| size <= max) {
+            // PC estable: los vecinos se fijan al empezar cada nivel
+            let neighbours: Vec<Vec<usize>> = adjacent.iter()
+                .map(|row| (0..n).filter(|j| row[*j]).collect())
+                .collect();
+            if neighbours.iter().all(|list| list.len() <= size) {
+                break;
+            }
+            for x in 0..n {
+                for &y in &neighbours[x] {
+                    if !adjacent[x][y] {
+                        continue;
+                    }
+                    let candidates: Vec<usize> = neighbours[x].iter().copied().filter(|v| *v != y).collect();
+                    for given in subsets(&candidates, size) {
+                        let (statistic, degrees_of_freedom) = statistic(&columns, &cards, x, y, &given, options.test);
+                        let p_value = if degrees_of_freedom == 0 { 1.0 } else { chi_square_survival(statistic, degrees_of_freedom) };
+                        let independent = p_value > options.alpha;
+                        tests.push(IndependenceCheck {
+                            x: names[x].clone(),
+                            y: names[y].clone(),
+                            given: given.iter().map(|v| names[*v].clone()).collect(),
+                            test: options.test,
+                            statistic,
+                            degrees_of_freedom,
+                            p_value,
+                            independent,
+                        });
+                        if independent {
+                            adjacent[x][y] = false;
+                            adjacent[y][x] = false;
+                            separating.insert((x.min(y), x.max(y)), given);
+                            break;
+                        }
+                    }
+                }
+            }
+            size += 1;
+        }
+
+        let directed = orient(&adjacent, &separating);
+        let mut result = PcResult { variables: names.clone(), directed: Vec::new(), undirected: Vec::new(), tests };
+        for from in 0..n {
+            for to in 0..n {
+                if directed[from][to] {
+                    result.directed.push(Edge::new(&names[from], &names[to]));
+                } else if from < to && adjacent[from][to] && !directed[to][from] {
+                    result.undirected.push(Edge::new(&names[from], &names[to]));
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+// Estadístico y grados de libertad de x ⟂ y | given, sumados sobre cada configuración
+// observada de `given`. Las filas y columnas vacías de cada estrato no cuentan.
+fn statistic(columns: &[Vec<usize>], cards: &[usize], x: usize, y: usize, given: &[usize], test: IndependenceTest) -> (f64, usize) {
+    let (rx, ry) = (cards[x], cards[y]);
+    // Estratos ordenados: el estadístico se suma siempre en el mismo orden
+    let mut strata: BTreeMap<usize, Vec<f64>> = BTreeMap::new();
+    for case in 0..columns[x].len() {
+        let stratum = given.iter().fold(0, |row, v| row * cards[*v] + columns[*v][case]);
+        strata.entry(stratum).or_insert_with(|| vec![0.0; rx * ry])[columns[x][case] * ry + columns[y][case]] += 1.0;
+    }
+
+    let mut total = 0.0;
+    let mut degrees = 0;
+    for table in strata.values() {
+        let rows: Vec<f64> = (0..rx).map(|i| table[i * ry..(i + 1) * ry].iter().sum()).collect();
+        let cols: Vec<f64> = (0..ry).map(|j| (0..rx).map(|i| table[i * ry + j]).sum()).collect();
+        let count: f64 = rows.iter().sum();
+        for i in 0..rx {
+            for j in 0..ry {
+                let expected = rows[i] * cols[j] / count;
+                if expected <= 0.0 {
+                    continue;
+                }
+                let observed = table[i * ry + j];
+                total += match test {
+                    IndependenceTest::ChiSquare => (observed - expected).powi(2) / expected,
+                    IndependenceTest::GTest if observed > 0.0 => 2.0 * observed * (observed / expected).ln(),
+                    IndependenceTest::GTest => 0.0,
+                };
+            }
+        }
+        let used = |sums: &[f64]| sums.iter().filter(|s| **s > 0.0).count().saturating_sub(1);
+        degrees += used(&rows) * used(&cols);
+    }
+    (total, degrees)
+}
+
+// Subconjuntos de `items` con `size` elementos, en orden lexicográfico.
+fn subsets(items: &[usize], size: usize) -> Vec<Vec<usize>> {
+    if size == 0 {
+        return vec![Vec::new()];
+    }
+    if items.len() < size {
+        return Vec::new();
+    }
+    let mut result = Vec::new();
+    for (i, first) in items.iter().enumerate() {
+        for mut rest in subsets(&items[i + 1..], size - 1) {
+            rest.insert(0, *first);
+            result.push(rest);
+        }
+    }
+    result
+}
+
+// `directed[a][b]` si el CPDAG tiene a → b. Primero las v-estructuras (un arco ya
+// orientado al revés por otra v-estructura se deja como estaba) y después las reglas
+// de Meek 1–3 hasta que no cambie nada.
+fn orient(adjacent: &[Vec<bool>], separating: &HashMap<(usize, usize), Vec<usize>>) -> Vec<Vec<bool>> {
+    let n = adjacent.len();
+    let mut directed = vec![vec![false; n]; n];
+    let undirected = |d: &[Vec<bool>], a: usize, b: usize| adjacent[a][b] && !d[a][b] && !d[b][a];
+
+    for x in 0..n {
+        for y in x + 1..n {
+            if adjacent[x][y] {
+                continue;
+            }
+            let sepset = separating.get(&(x, y)).map(Vec::as_slice).unwrap_or(&[]);
+            for z in (0..n).filter(|z| adjacent[x][*z] && adjacent[y][*z] && !sepset.contains(z)) {
+                for from in [x, y] {
+                    if !directed[z][from] {
+                        directed[from][z] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for a in 0..n {
+            for b in 0..n {
+                if !undirected(&directed, a, b) {
+                    continue;
+                }
+                // R1: c → a − b con c y b no adyacentes
+                let r1 = (0..n).any(|c| directed[c][a] && !adjacent[c][b] && c != b);
+                // R2: a → c → b con a − b
+                let r2 = (0..n).any(|c| directed[a][c] && directed[c][b]);
+                // R3: a − c → b y a − d → b con c y d no adyacentes
+                let r3 = (0..n).any(|c| {
+                    undirected(&directed, a, c) && directed[c][b]
+                        && (c + 1..n).any(|d| undirected(&directed, a, d) && directed[d][b] && !adjacent[c][d])
+                });
+                if r1 || r2 || r3 {
+                    directed[a][b] = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+    directed
+}
+
+// P(χ²_k > x): función gamma incompleta regularizada Q(k/2, x/2).
+fn chi_square_survival(x: f64, degrees: usize) -> f64 {
+    let (a, x) = (degrees as f64 / 2.0, x / 2.0);
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let log_prefix = a * x.ln() - x - ln_gamma(a);
+    if x < a + 1.0 {
+        // Serie de P(a, x)
+        let (mut term, mut sum, mut n) = (1.0 / a, 1.0 / a, a);
+        while term.abs() > sum.abs() * 1e-15 {
+            n += 1.0;
+            term *= x / n;
+            sum += term;
+        }
+        (1.0 - sum * log_prefix.exp()).max(0.0)
+    } else {
+        // Fracción continua de Q(a, x) (Lentz)
+        let tiny = 1e-300;
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / tiny;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..500 {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            d = if d.abs() < tiny { 1.0 / tiny } else { 1.0 / d };
+            c = b + an / c;
+            if c.abs() < tiny {
+                c = tiny;
+            }
+            let step = d * c;
+            h *= step;
+            if (step - 1.0).abs() < 1e-15 {
+                break;
+            }
+        }
+        (log_prefix.exp() * h).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::learning::sample_dataset;
+
+    fn spec(collider: bool) -> ModelSpec {
+        let c = if collider {
+            r#"
+  - name: C
+    states: [si, no]
+    parents: [A, B]
+    cpt:
+      - { given: [si, si], probabilities: { si: 0.95, no: 0.05 } }
+      - { given: [si, no], probabilities: { si: 0.5, no: 0.5 } }
+      - { given: [no, si], probabilities: { si: 0.5, no: 0.5 } }
+      - { given: [no, no], probabilities: { si: 0.05, no: 0.95 } }
+"#
+        } else {
+            r#"
+  - name: C
+    states: [si, no]
+    parents: [B]
+    cpt:
+      - { given: [si], probabilities: { si: 0.85, no: 0.15 } }
+      - { given: [no], probabilities: { si: 0.2, no: 0.8 } }
+"#
+        };
+        let b = if collider { "cpt: [ { given: [], probabilities: { si: 0.5, no: 0.5 } } ]" } else {
+            "parents: [A]\n    cpt:\n      - { given: [si], probabilities: { si: 0.9, no: 0.1 } }\n      - { given: [no], probabilities: { si: 0.1, no: 0.9 } }"
+        };
+        ModelSpec::from_yaml(&format!(r#"
+name: pc
+nodes:
+  - name: A
+    states: [si, no]
+    cpt: [ {{ given: [], probabilities: {{ si: 0.5, no: 0.5 }} }} ]
+  - name: B
+    states: [si, no]
+    {}{}"#, b, c)).unwrap()
+    }
+
+    #[test]
+    fn test_chi_square_survival() {
+        assert!((chi_square_survival(3.841_458_820_694_124, 1) - 0.05).abs() < 1e-9);
+        assert!((chi_square_survival(5.991_464_547_107_979, 2) - 0.05).abs() < 1e-9);
+        assert!((chi_square_survival(2.0, 4) - 0.735_758_882_342_884_6).abs() < 1e-9);
+        assert_eq!(subsets(&[1, 2, 3], 2), vec![vec![1, 2], vec![1, 3], vec![2, 3]]);
+    }
+
+    #[test]
+    fn test_chain_stays_undirected() {
+        let data = sample_dataset(&spec(false), 2000, 17);
+        for test in [IndependenceTest::ChiSquare, IndependenceTest::GTest] {
+            let result = spec(false).learn_pc(&data, &PcOptions { test, ..PcOptions::default() }).unwrap();
+            assert!(result.directed.is_empty(), "{:?}", result.directed);
+            assert_eq!(result.undirected, vec![Edge::new("A", "B"), Edge::new("B", "C")]);
+            assert!(result.tests.iter().any(|t| t.x == "A" && t.y == "C" && t.given == ["B"] && t.independent));
+        }
+    }
+
+    #[test]
+    fn test_collider_is_oriented() {
+        let data = sample_dataset(&spec(true), 2000, 23);
+        let result = spec(true).learn_pc(&data, &PcOptions::default()).unwrap();
+        assert_eq!(result.directed, vec![Edge::new("A", "C"), Edge::new("B", "C")]);
+        assert!(result.undirected.is_empty());
+        let marginal = result.tests.iter().find(|t| t.x == "A" && t.y == "B" && t.given.is_empty()).unwrap();
+        assert!(marginal.independent && marginal.degrees_of_freedom == 1);
+
+        assert!(spec(true).learn_pc(&data, &PcOptions { alpha: 1.5, ..PcOptions::default() }).is_err());
+    }
+}
@@ -1,19 +1,50 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 // Importaciones necesarias de tu librería suma_core
 use suma_core::core::probability::bayes::BayesianNetwork;
 use suma_core::core::probability::bayes::BN_base::{BayesianNetworkBase, State};
-use console_error_panic_hook; // Para ver errores en el navegador
 
 mod build;
-use build::build_network_internal;
+pub mod formats;
+pub mod model;
+pub mod inference;
+pub mod validation;
+pub mod evidence;
+pub mod error;
+pub mod learning;
+use build::default_model;
+use model::{ModelSpec, NodeMetadata, NodeRole};
+use validation::ValidationReport;
+use evidence::UnknownState;
+use learning::{Adaptation, Dataset, EmOptions, EmTrace, PcOptions, PcResult, Prior, StructureOptions, StructureResult};
+use learning::online::DEFAULT_EXPERIENCE;
+pub use error::BnError;
+use inference::{
+    EvidenceLikelihood, Explanation, InferenceAlgorithm, InferenceOptions, InferenceResult, JointEntry, JointPosterior, MapResult,
+    MostProbableExplanation,
+    ProbabilityInterval, SamplingDiagnostics,
+};
+use inference::junction_tree::{junction_tree_posterior, JunctionTree};
+use inference::factor::{increment, Factor};
+use inference::gibbs::gibbs_posterior;
+use inference::mpe::top_k_assignments;
+use inference::sampling::{likelihood_weighting_posterior, SampledPosterior};
+use inference::tables::NetworkTables;
+use inference::variable_elimination::{eliminate_all_but, variable_elimination};
 
 // --- 1. Definición del Struct ---
 
 #[wasm_bindgen]
 pub struct BiodigestorModel {
     network: BayesianNetwork,
+    tables: NetworkTables,
+    junction_tree: JunctionTree,
+    name: String,
+    metadata: HashMap<String, NodeMetadata>,
+    /// Experiencia acumulada por `observe_case`.
+    adaptation: Adaptation,
 }
 
 #[derive(serde::Serialize)]
@@ -21,6 +52,18 @@ pub struct WasmNode {
     pub id: String, 
     pub label: String,
     pub group: String, 
+    pub role: NodeRole,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub state_descriptions: BTreeMap<String, String>,
+    /// Posición importada del archivo de modelo (Hugin, GeNIe...), si la hay.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<f64>,
 }
 
 #[derive(serde::Serialize)]
@@ -35,112 +78,501 @@ pub struct WasmGraph {
     pub edges: Vec<WasmEdge>,
 }
 
-// --- 2. Implementación de Métodos Wasm ---
+/// Qué nodos devuelve `node_list` según su papel (ver `node_role`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeFilter {
+    #[default]
+    All,
+    /// Sensores: los nodos que se introducen como evidencia.
+    Observable,
+    /// Causas ocultas.
+    Hidden,
+    /// Todo lo que tiene sentido consultar: causas ocultas y magnitudes físicas.
+    Target,
+}
+
+impl NodeFilter {
+    pub fn parse(text: &str) -> Option<NodeFilter> {
+        match text {
+            "all" => Some(NodeFilter::All),
+            "observable" => Some(NodeFilter::Observable),
+            "hidden" => Some(NodeFilter::Hidden),
+            "target" => Some(NodeFilter::Target),
+            _ => None,
+        }
+    }
+
+    fn accepts(&self, role: NodeRole) -> bool {
+        match self {
+            NodeFilter::All => true,
+            NodeFilter::Observable => role == NodeRole::Sensor,
+            NodeFilter::Hidden => role == NodeRole::Hidden,
+            NodeFilter::Target => role != NodeRole::Sensor,
+        }
+    }
+}
+
+/// Nodo de `get_node_names`: nombre, etiqueta, papel y estados en el orden declarado.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct NodeInfo {
+    pub name: String,
+    pub label: String,
+    pub role: NodeRole,
+    pub states: Vec<String>,
+}
 
+/// Resultado de `learn_em` en JS: el modelo ajustado y la traza de EM.
 #[wasm_bindgen]
+pub struct EmLearning {
+    /// `None` una vez entregado por `model()`.
+    model: Option<BiodigestorModel>,
+    trace: EmTrace,
+}
+
+// --- 2. API Rust (usable en tests y fuera de wasm) ---
+
 impl BiodigestorModel {
+    /// Rechaza redes que no pasan `validate()` (filas faltantes, sumas distintas de 1...).
+    pub fn from_network(network: BayesianNetwork) -> Result<BiodigestorModel, BnError> {
+        ModelSpec::from_network(&network, "network").map_err(BnError::invalid_model)?.validate().into_result()?;
+        Self::compile(network)
+    }
 
-    #[wasm_bindgen(constructor)]
-    pub fn new() -> Result<BiodigestorModel, JsValue> {
-        console_error_panic_hook::set_once();
+    /// Construye el modelo desde un archivo de modelo (ver `model::ModelSpec`). El archivo
+    /// se valida antes de construir la red, así que los errores se informan todos juntos.
+    pub fn from_spec(spec: &ModelSpec) -> Result<BiodigestorModel, BnError> {
+        spec.validate().into_result()?;
+        let mut model = Self::compile(spec.build_network().map_err(BnError::invalid_model)?)?;
+        model.name = spec.name.clone();
+        model.metadata = spec.metadata();
+        Ok(model)
+    }
 
-        let network = build_network_internal()
-            .map_err(|e| JsValue::from_str(&format!("Error building network: {}", e)))?;
+    pub fn from_json(json: &str) -> Result<BiodigestorModel, BnError> {
+        Self::from_spec(&ModelSpec::from_json(json).map_err(BnError::invalid_model)?)
+    }
 
-        Ok(BiodigestorModel { network })
+    pub fn from_yaml(yaml: &str) -> Result<BiodigestorModel, BnError> {
+        Self::from_spec(&ModelSpec::from_yaml(yaml).map_err(BnError::invalid_model)?)
     }
 
-    // Función de Inferencia
-    #[wasm_bindgen]
-    pub fn infer(&self, evidence_js: JsValue, target_node: &str) -> Result<JsValue, JsValue> {
+    pub fn from_bif(bif: &str) -> Result<BiodigestorModel, BnError> {
+        Self::from_spec(&ModelSpec::from_bif(bif).map_err(BnError::invalid_model)?)
+    }
 
-        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
-            .map_err(|e| JsValue::from_str(&format!("Invalid evidence format: {}", e)))?;
+    pub fn from_xmlbif(xml: &str) -> Result<BiodigestorModel, BnError> {
+        Self::from_spec(&ModelSpec::from_xmlbif(xml).map_err(BnError::invalid_model)?)
+    }
 
-        let mut internal_evidence: HashMap<usize, State> = HashMap::new();
+    pub fn from_hugin(net: &str) -> Result<BiodigestorModel, BnError> {
+        Self::from_spec(&ModelSpec::from_hugin(net).map_err(BnError::invalid_model)?)
+    }
 
-        for (node_name, state_val) in evidence_map {
-            // CONVERSIÓN CORREGIDA: Usamos el from_str que mapea el string al Estado.
-            let state = State::from_str(&state_val);
+    pub fn from_xdsl(xdsl: &str) -> Result<BiodigestorModel, BnError> {
+        Self::from_spec(&ModelSpec::from_xdsl(xdsl).map_err(BnError::invalid_model)?)
+    }
 
-            let node_id = self.network.get_id_from_name(&node_name)
-                .ok_or_else(|| JsValue::from_str(&format!("Node not found: {}", node_name)))?;
+    fn compile(network: BayesianNetwork) -> Result<BiodigestorModel, BnError> {
+        let tables = NetworkTables::from_network(&network).map_err(BnError::invalid_model)?;
+        let junction_tree = JunctionTree::compile(&tables);
+        let adaptation = Adaptation::new(&tables, DEFAULT_EXPERIENCE, 1.0).map_err(BnError::invalid_model)?;
+        Ok(BiodigestorModel { network, tables, junction_tree, name: "network".to_string(), metadata: HashMap::new(), adaptation })
+    }
 
-            internal_evidence.insert(node_id, state);
+    /// Diagnósticos de la red en uso (ver `validation`).
+    pub fn validate(&self) -> Result<ValidationReport, BnError> {
+        Ok(self.to_spec()?.validate())
+    }
+
+    /// Descripción de la red en uso (CPTs actuales y metadatos), lista para exportar.
+    pub fn to_spec(&self) -> Result<ModelSpec, BnError> {
+        let mut spec = ModelSpec::from_network(&self.network, &self.name).map_err(BnError::invalid_model)?;
+        for node in &mut spec.nodes {
+            if let Some(metadata) = self.metadata.get(&node.name) {
+                node.metadata = metadata.clone();
+            }
         }
+        Ok(spec)
+    }
+
+    /// Modelo nuevo con las CPTs reestimadas a partir de casos completos (ver
+    /// `ModelSpec::learn_parameters`). Con `Prior::Model` las CPTs actuales hacen de prior.
+    pub fn learn_parameters(&self, data: &Dataset, prior: &Prior) -> Result<BiodigestorModel, BnError> {
+        prior.check().map_err(BnError::invalid_query)?;
+        let learned = self.to_spec()?.learn_parameters(data, prior).map_err(BnError::invalid_data)?;
+        Self::from_spec(&learned)
+    }
+
+    /// Modelo nuevo ajustado por EM con casos incompletos (ver `ModelSpec::learn_em`),
+    /// junto con la traza de log-verosimilitud.
+    pub fn learn_em(&self, data: &Dataset, options: &EmOptions) -> Result<(BiodigestorModel, EmTrace), BnError> {
+        options.check().map_err(BnError::invalid_query)?;
+        let (learned, trace) = self.to_spec()?.learn_em(data, options).map_err(BnError::invalid_data)?;
+        Ok((Self::from_spec(&learned)?, trace))
+    }
+
+    /// Estructura aprendida de casos completos y sus diferencias con la red en uso (ver
+    /// `ModelSpec::learn_structure`). El modelo no cambia.
+    pub fn learn_structure(&self, data: &Dataset, options: &StructureOptions) -> Result<StructureResult, BnError> {
+        let spec = self.to_spec()?;
+        options.check(&spec).map_err(BnError::invalid_query)?;
+        spec.learn_structure(data, options).map_err(BnError::invalid_data)
+    }
+
+    /// CPDAG aprendido con el algoritmo PC y la lista de tests de independencia (ver
+    /// `ModelSpec::learn_pc`). El modelo no cambia.
+    pub fn learn_pc(&self, data: &Dataset, options: &PcOptions) -> Result<PcResult, BnError> {
+        options.check().map_err(BnError::invalid_query)?;
+        self.to_spec()?.learn_pc(data, options).map_err(BnError::invalid_data)
+    }
+
+    /// Reinicia la adaptación en línea: las CPTs actuales pasan a valer `experience`
+    /// casos por fila y cada caso nuevo olvida según `fading` (ver `Adaptation`).
+    pub fn set_adaptation(&mut self, experience: f64, fading: f64) -> Result<(), BnError> {
+        self.adaptation = Adaptation::new(&self.tables, experience, fading).map_err(BnError::invalid_query)?;
+        Ok(())
+    }
+
+    /// Incorpora un caso confirmado (p. ej. una fuga verificada en mantenimiento) a las
+    /// CPTs del modelo en uso. Los nodos que faltan en `case` se reparten según la
+    /// posterior; `weight` cuenta el caso como varios (o como una fracción de uno).
+    /// `to_spec` y los `export_*` devuelven ya las CPTs actualizadas.
+    pub fn observe_case(&mut self, case: &HashMap<String, String>, weight: f64) -> Result<(), BnError> {
+        if !(weight.is_finite() && weight > 0.0) {
+            return Err(BnError::invalid_query(format!("Case weight must be positive, got {}", weight)));
+        }
+        let evidence = self.evidence_probability(case)?;
+        let observed = self.tables.evidence_vector(&self.internal_evidence(case)?)
+            .ok_or_else(|| BnError::invalid_query("Evidence uses a state the node does not declare"))?;
+
+        let mut adaptation = self.adaptation.clone();
+        let cpts = adaptation.observe(&self.tables, &observed, evidence, weight);
+        let spec = self.to_spec()?.with_cpts(&self.tables, &cpts).map_err(BnError::invalid_model)?;
+        let mut updated = Self::from_spec(&spec)?;
+        updated.adaptation = adaptation;
+        *self = updated;
+        Ok(())
+    }
+
+    /// Metadatos del nodo declarados en el archivo de modelo, si los hay.
+    pub fn node_metadata(&self, node: &str) -> Option<&NodeMetadata> {
+        self.metadata.get(node)
+    }
+
+    /// Nodos de la red (en orden de creación) que pasan el filtro.
+    pub fn node_list(&self, filter: NodeFilter) -> Vec<NodeInfo> {
+        self.tables.nodes().iter()
+            .filter_map(|node| {
+                let role = self.node_role(&node.name)?;
+                filter.accepts(role).then(|| NodeInfo {
+                    name: node.name.clone(),
+                    label: self.metadata.get(&node.name).and_then(|m| m.label.clone()).unwrap_or_else(|| node.name.clone()),
+                    role,
+                    states: node.states.iter().cloned().map(state_to_string).collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Papel del nodo: el declarado en el modelo o, si no hay, uno deducido de la
+    /// estructura (raíces ocultas, hojas sensores, el resto magnitudes físicas).
+    /// `None` si el nodo no existe.
+    pub fn node_role(&self, node: &str) -> Option<NodeRole> {
+        let id = self.network.get_id_from_name(node)?;
+        if let Some(role) = self.metadata.get(node).and_then(|m| m.role) {
+            return Some(role);
+        }
+        Some(if self.network.get_parents(id).is_empty() {
+            NodeRole::Hidden
+        } else if self.network.get_children(id).is_empty() {
+            NodeRole::Sensor
+        } else {
+            NodeRole::Physical
+        })
+    }
+
+    /// Distribución posterior de `target_node` dada la evidencia `{nodo: estado}`.
+    pub fn posterior(
+        &self,
+        evidence_map: &HashMap<String, String>,
+        target_node: &str,
+        options: &InferenceOptions,
+    ) -> Result<InferenceResult, BnError> {
+        options.check().map_err(BnError::invalid_query)?;
+        let internal_evidence = self.internal_evidence(evidence_map)?;
 
         let target_id = self.network.get_id_from_name(target_node)
-            .ok_or_else(|| JsValue::from_str(&format!("Target node not found: {}", target_node)))?;
+            .ok_or_else(|| BnError::unknown_node(target_node))?;
+        // Sin esta comprobación el muestreo devolvería una distribución vacía
+        let evidence_probability = self.evidence_probability(evidence_map)?;
 
-        // Ejecutar Inferencia (Asumimos que devuelve HashMap<State, f64>)
-        let distribution = self.network.likelihood_weighting_sampling(
-            &internal_evidence,
-            target_id,
-            10_000
-        );
+        // Los motores solo fallan si el nodo consultado no está en las tablas
+        let not_found = |_| BnError::unknown_node(target_node);
+        let (distribution, sampling, estimate) = match options.algorithm {
+            InferenceAlgorithm::LikelihoodWeighting => {
+                let sampled = likelihood_weighting_posterior(&self.tables, &internal_evidence, target_id, options).map_err(not_found)?;
+                sampled_result(sampled, "Likelihood weighting drew no sample with positive weight; try more samples or another algorithm")?
+            }
+            InferenceAlgorithm::Gibbs => {
+                let sampled = gibbs_posterior(&self.tables, &internal_evidence, target_id, options).map_err(not_found)?;
+                sampled_result(sampled, "Gibbs kept no sample: no initial state matches the evidence or the time budget ran out during burn-in")?
+            }
+            InferenceAlgorithm::Exact => (variable_elimination(&self.tables, &internal_evidence, target_id).map_err(not_found)?, None, None),
+            InferenceAlgorithm::JunctionTree => (junction_tree_posterior(&self.junction_tree, &self.tables, &internal_evidence, target_id).map_err(not_found)?, None, None),
+        };
+        // Gibbs no estima P(evidencia); en ese caso (y en los exactos) se da el valor exacto
+        let evidence = estimate.unwrap_or_else(|| EvidenceLikelihood::exact(evidence_probability));
 
         // Convertir resultados de State a String para JS
-        let mut result_js: HashMap<String, f64> = HashMap::new();
+        let probabilities = distribution
+            .into_iter()
+            .map(|(state, prob)| (state_to_string(state), prob))
+            .collect();
+
+        Ok(InferenceResult { algorithm: options.algorithm, probabilities, sampling, evidence })
+    }
+
+    /// Marginales de todos los nodos de la red con una sola calibración del árbol de cliques.
+    pub fn posterior_all(
+        &self,
+        evidence_map: &HashMap<String, String>,
+    ) -> Result<HashMap<String, HashMap<String, f64>>, BnError> {
+        let internal_evidence = self.internal_evidence(evidence_map)?;
+        self.evidence_probability(evidence_map)?;
 
-        for (state, prob) in distribution {
-            let state_str = match state {
-                State::True => "True".to_string(), // Si usas "True" en el modelo
-                State::False => "False".to_string(), // Si usas "False" en el modelo
-                State::Value(s) => s, // Devuelve el string original ("Bueno", "Fuga", "bajo", etc.)
-                _ => format!("{:?}", state),
-            };
-            result_js.insert(state_str, prob);
+        let mut result = HashMap::new();
+        for node_id in self.network.get_nodes() {
+            let node_name = self.network.get_name_from_id(node_id)
+                .ok_or_else(|| BnError::invalid_model(format!("Node {} has no name", node_id)))?;
+
+            // La primera llamada calibra; el resto reutiliza la calibración en caché.
+            let distribution = junction_tree_posterior(&self.junction_tree, &self.tables, &internal_evidence, node_id)
+                .map_err(|_| BnError::unknown_node(node_name))?;
+            let states = distribution
+                .into_iter()
+                .map(|(state, prob)| (state_to_string(state), prob))
+                .collect();
+            result.insert(node_name.clone(), states);
         }
 
-        Ok(serde_wasm_bindgen::to_value(&result_js)?)
+        Ok(result)
     }
 
-    /// Obtiene la lista de todos los nombres de nodos
-    #[wasm_bindgen]
-    pub fn get_node_names(&self) -> JsValue {
-        // Necesitas tener un método en suma_core que te dé la lista de nombres
-        // Usaremos una lista hardcoded para la UI para evitar problemas de Trait.
-        let names = vec![
-            "EstadoMicrobiano", "EstadoOperativo",
-            "TemperaturaReal", "CaudalReal",
-            "T_sensor", "Flow_sensor", "pH_sensor", "Gas_sensor", "Presion_sensor"
-        ];
+    /// Estado conjunto más probable de los nodos no observados (max-product), junto con
+    /// las `alternatives` siguientes explicaciones en orden de probabilidad.
+    pub fn mpe(&self, evidence_map: &HashMap<String, String>, alternatives: usize) -> Result<MostProbableExplanation, BnError> {
+        let internal_evidence = self.internal_evidence(evidence_map)?;
+        let observed = self.tables.evidence_vector(&internal_evidence)
+            .ok_or_else(|| BnError::invalid_query("Evidence uses a state the node does not declare"))?;
+
+        let evidence_probability = self.evidence_probability(evidence_map)?;
+        let ranked = top_k_assignments(&self.tables, &observed, alternatives + 1);
+
+        let mut explanations = ranked.into_iter().map(|ranked| {
+            let assignment = self.tables.nodes().iter().enumerate()
+                .filter(|(index, _)| observed[*index].is_none())
+                .map(|(index, node)| (node.name.clone(), state_to_string(node.states[ranked.states[index]].clone())))
+                .collect();
+            Explanation {
+                assignment,
+                probability: ranked.joint / evidence_probability,
+                joint_probability: ranked.joint,
+            }
+        });
 
-        serde_wasm_bindgen::to_value(&names).unwrap()
+        let best = explanations.next().expect("ranked is not empty");
+        Ok(MostProbableExplanation {
+            best,
+            alternatives: explanations.collect(),
+            evidence: EvidenceLikelihood::exact(evidence_probability),
+        })
     }
 
-    #[wasm_bindgen]
-    pub fn get_node_cpt(&self, node: &str) -> JsValue {
-            let cpt = self.network.get_node_cpt_by_name(node);
-            serde_wasm_bindgen::to_value(&cpt).unwrap()
+    /// Distribución conjunta exacta P(targets | evidencia, given). `given` son las
+    /// condiciones de una consulta condicional; se combinan con la evidencia y no
+    /// pueden contradecirla. La tabla recorre las combinaciones de estados en el orden
+    /// de `targets` (el último varía más rápido).
+    pub fn joint_posterior(
+        &self,
+        evidence_map: &HashMap<String, String>,
+        targets: &[String],
+        given: &HashMap<String, String>,
+    ) -> Result<JointPosterior, BnError> {
+        let mut conditions = evidence_map.clone();
+        for (node, state) in given {
+            match conditions.get(node) {
+                Some(existing) if existing != state => {
+                    return Err(BnError::invalid_query(format!("Condition {}={} contradicts evidence {}={}", node, state, node, existing)));
+                }
+                _ => {
+                    conditions.insert(node.clone(), state.clone());
+                }
+            }
+        }
+
+        let (joint, keep) = self.joint_factor(&conditions, targets)?;
+        let evidence_probability = joint.total();
+
+        let cards: Vec<usize> = keep.iter().map(|v| self.tables.card(*v)).collect();
+        let len: usize = cards.iter().product();
+        let mut entries = Vec::with_capacity(len);
+        let mut assignment = vec![0; keep.len()];
+        for _ in 0..len {
+            // Índice en el factor, cuyas variables van ordenadas por índice de nodo
+            let position = joint.vars.iter().zip(&joint.cards).fold(0, |acc, (var, card)| {
+                let slot = keep.iter().position(|k| k == var).expect("Factor scope is the target set");
+                acc * card + assignment[slot]
+            });
+            let states = keep.iter().zip(&assignment)
+                .map(|(&var, &state)| state_to_string(self.tables.nodes()[var].states[state].clone()))
+                .collect();
+            entries.push(JointEntry { states, probability: joint.values[position] / evidence_probability });
+            increment(&mut assignment, &cards);
+        }
+
+        Ok(JointPosterior {
+            variables: targets.to_vec(),
+            given: given.clone(),
+            entries,
+            evidence: EvidenceLikelihood::exact(evidence_probability),
+        })
     }
 
-    #[wasm_bindgen(js_name = "getGraphStructure")]
-    pub fn get_graph_structure(&self) -> Result<JsValue, JsValue> {
+    /// MAP parcial: distribución conjunta exacta de `variables` dada la evidencia, sumando
+    /// el resto de nodos, y su configuración más probable.
+    pub fn partial_map(&self, evidence_map: &HashMap<String, String>, variables: &[String]) -> Result<MapResult, BnError> {
+        let (joint, _) = self.joint_factor(evidence_map, variables)?;
+        let evidence_probability = joint.total();
+
+        // El factor ordena sus variables por índice; se recorre en ese orden.
+        let mut distribution = Vec::with_capacity(joint.values.len());
+        let mut assignment = vec![0; joint.vars.len()];
+        for value in &joint.values {
+            let states = joint.vars.iter().zip(&assignment)
+                .map(|(&var, &state)| {
+                    let node = &self.tables.nodes()[var];
+                    (node.name.clone(), state_to_string(node.states[state].clone()))
+                })
+                .collect();
+            distribution.push(Explanation {
+                assignment: states,
+                probability: value / evidence_probability,
+                joint_probability: *value,
+            });
+            increment(&mut assignment, &joint.cards);
+        }
+        distribution.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+
+        Ok(MapResult {
+            variables: variables.to_vec(),
+            best: distribution[0].clone(),
+            distribution,
+            evidence: EvidenceLikelihood::exact(evidence_probability),
+        })
+    }
+
+    // Factor sin normalizar P(variables, evidencia) y los índices de `variables` en el
+    // orden pedido. Error si la lista está vacía o repetida, o si la evidencia es imposible.
+    fn joint_factor(&self, evidence_map: &HashMap<String, String>, variables: &[String]) -> Result<(Factor, Vec<usize>), BnError> {
+        if variables.is_empty() {
+            return Err(BnError::invalid_query("Query needs at least one variable"));
+        }
+
+        let internal_evidence = self.internal_evidence(evidence_map)?;
+        let observed = self.tables.evidence_vector(&internal_evidence)
+            .ok_or_else(|| BnError::invalid_query("Evidence uses a state the node does not declare"))?;
+
+        let mut keep = Vec::with_capacity(variables.len());
+        for name in variables {
+            let index = self.network.get_id_from_name(name)
+                .and_then(|id| self.tables.index_of(id))
+                .ok_or_else(|| BnError::unknown_node(name))?;
+            if keep.contains(&index) {
+                return Err(BnError::invalid_query(format!("Variable listed twice: {}", name)));
+            }
+            keep.push(index);
+        }
+
+        let joint = eliminate_all_but(&self.tables, &observed, &keep);
+        if joint.total() <= 0.0 {
+            return Err(BnError::impossible_evidence(evidence_map, self.minimal_conflict(evidence_map)));
+        }
+        Ok((joint, keep))
+    }
+
+    /// P(evidencia) exacta y su logaritmo, p. ej. para marcar lecturas anómalas.
+    pub fn evidence_likelihood(&self, evidence_map: &HashMap<String, String>) -> Result<EvidenceLikelihood, BnError> {
+        Ok(EvidenceLikelihood::exact(self.evidence_probability(evidence_map)?))
+    }
+
+    // P(evidencia) exacta: el normalizador de la calibración del árbol de cliques, que
+    // queda en caché para la consulta. Si es cero, error con el subconjunto de lecturas
+    // que la hace imposible (solo entonces se recurre a eliminación de variables).
+    fn evidence_probability(&self, evidence_map: &HashMap<String, String>) -> Result<f64, BnError> {
+        let internal_evidence = self.internal_evidence(evidence_map)?;
+        let observed = self.tables.evidence_vector(&internal_evidence)
+            .ok_or_else(|| BnError::invalid_query("Evidence uses a state the node does not declare"))?;
+        let probability = self.junction_tree.calibrated(&observed).evidence_probability();
+        if probability > 0.0 {
+            Ok(probability)
+        } else {
+            Err(BnError::impossible_evidence(evidence_map, self.minimal_conflict(evidence_map)))
+        }
+    }
+
+    fn exact_evidence_probability(&self, evidence_map: &HashMap<String, String>) -> Result<f64, BnError> {
+        let internal_evidence = self.internal_evidence(evidence_map)?;
+        let observed = self.tables.evidence_vector(&internal_evidence)
+            .ok_or_else(|| BnError::invalid_query("Evidence uses a state the node does not declare"))?;
+        Ok(eliminate_all_but(&self.tables, &observed, &[]).total())
+    }
+
+    // Eliminación hacia atrás, en orden alfabético: se quita cada lectura cuya ausencia
+    // deja la evidencia igual de imposible. El resultado sigue siendo imposible y no le
+    // sobra ninguna lectura (es mínimo, aunque no necesariamente el más pequeño).
+    fn minimal_conflict(&self, evidence_map: &HashMap<String, String>) -> BTreeMap<String, String> {
+        let mut conflict: BTreeMap<String, String> = evidence_map.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+        for node in evidence_map.keys().cloned().collect::<BTreeSet<_>>() {
+            let mut candidate: HashMap<String, String> = conflict.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            candidate.remove(&node);
+            if self.exact_evidence_probability(&candidate).is_ok_and(|p| p <= 0.0) {
+                conflict.remove(&node);
+            }
+        }
+        conflict
+    }
+
+    /// Nodos (con grupo y posición) y aristas de la red, para `getGraphStructure`.
+    pub fn graph_structure(&self) -> WasmGraph {
         let mut nodes: Vec<WasmNode> = Vec::new();
         let mut edges: Vec<WasmEdge> = Vec::new();
 
-        // ⚠️ Nota: Necesitas exponer get_id_to_name y get_name_from_id en suma_core 
-        // o implementar aquí la iteración de IDs y Nombres.
-        // Asumiremos que puedes iterar los IDs para obtener la estructura.
-        
         let node_ids = self.network.get_nodes(); // Obtener todos los IDs de nodo
 
         for node_id in node_ids {
             if let Some(node_name) = self.network.get_name_from_id(node_id) {
                 
-                // Determinar el grupo
-                let group = match node_name.as_str() {
-                    "EstadoMicrobiano" | "EstadoOperativo" => "Oculto",
-                    n if n.contains("Real") => "Físico",
-                    _ => "Sensor",
+                // Grupo (color en el grafo) según el papel del nodo
+                let role = self.node_role(node_name).unwrap_or(NodeRole::Physical);
+                let group = match role {
+                    NodeRole::Hidden => "Oculto",
+                    NodeRole::Physical => "Físico",
+                    NodeRole::Sensor => "Sensor",
                 };
 
                 // Crear nodo Wasm
+                let metadata = self.metadata.get(node_name).cloned().unwrap_or_default();
+                let position = metadata.position;
                 nodes.push(WasmNode {
                     id: node_name.clone(),
-                    label: node_name.clone(),
+                    label: metadata.label.unwrap_or_else(|| node_name.clone()),
                     group: group.to_string(),
+                    role,
+                    description: metadata.description,
+                    units: metadata.units,
+                    state_descriptions: metadata.state_descriptions,
+                    x: position.map(|[x, _]| x),
+                    y: position.map(|[_, y]| y),
                 });
 
                 // Crear aristas
@@ -156,12 +588,930 @@ impl BiodigestorModel {
             }
         }
 
-        let graph_data = WasmGraph { nodes, edges };
+        WasmGraph { nodes, edges }
+    }
+
+    fn internal_evidence(&self, evidence_map: &HashMap<String, String>) -> Result<HashMap<usize, State>, BnError> {
+        let mut internal_evidence: HashMap<usize, State> = HashMap::new();
+
+        for (node_name, state_val) in evidence_map {
+            // CONVERSIÓN CORREGIDA: Usamos el from_str que mapea el string al Estado.
+            let state = State::from_str(state_val);
+
+            let node_id = self.network.get_id_from_name(node_name)
+                .ok_or_else(|| BnError::unknown_node(node_name))?;
+            self.check_state(node_id, node_name, &state, state_val)?;
+
+            internal_evidence.insert(node_id, state);
+        }
+
+        Ok(internal_evidence)
+    }
+
+    /// Comprueba que cada valor de evidencia sea un estado declarado de su nodo.
+    pub fn check_evidence(&self, evidence_map: &HashMap<String, String>) -> Result<(), UnknownState> {
+        for (node_name, state_val) in evidence_map {
+            if let Some(node_id) = self.network.get_id_from_name(node_name) {
+                self.check_state(node_id, node_name, &State::from_str(state_val), state_val)?;
+            }
+        }
+        Ok(())
+    }
+
+    // La comparación es la de `State` (así "true" y "True" siguen siendo equivalentes).
+    fn check_state(&self, node_id: usize, node_name: &str, state: &State, value: &str) -> Result<(), UnknownState> {
+        let Some(index) = self.tables.index_of(node_id) else {
+            return Ok(());
+        };
+        if self.tables.state_index(index, state).is_some() {
+            return Ok(());
+        }
+        let valid = self.tables.nodes()[index].states.iter().cloned().map(state_to_string).collect();
+        Err(UnknownState::new(node_name, value, valid))
+    }
+}
+
+type SampledParts = (HashMap<State, f64>, Option<SamplingDiagnostics>, Option<EvidenceLikelihood>);
+
+// Separa una estimación muestreada en la distribución, sus diagnósticos y la
+// estimación de P(evidencia), si el motor la da. Sin estimación (ninguna muestra
+// válida) es un error, no una distribución vacía.
+fn sampled_result(sampled: Option<SampledPosterior>, failure: &str) -> Result<SampledParts, BnError> {
+    let Some(sampled) = sampled else {
+        return Err(BnError::sampling_failed(failure));
+    };
+
+    let intervals = sampled.distribution.iter()
+        .map(|(state, p)| {
+            let interval = ProbabilityInterval::normal_95(*p, sampled.std_errors[state]);
+            (state_to_string(state.clone()), interval)
+        })
+        .collect();
+    let diagnostics = SamplingDiagnostics {
+        samples: sampled.samples,
+        effective_sample_size: sampled.effective_sample_size,
+        intervals,
+        converged: sampled.converged,
+        r_hat: sampled.r_hat,
+    };
+    Ok((sampled.distribution, Some(diagnostics), sampled.evidence))
+}
+
+// Tabla de casos en `"csv"` o `"json"`.
+fn parse_dataset(data: &str, format: &str) -> Result<Dataset, BnError> {
+    match format {
+        "csv" => Dataset::from_csv(data),
+        "json" => Dataset::from_json(data),
+        other => return Err(BnError::invalid_query(format!("Unknown data format: {}", other))),
+    }
+    .map_err(BnError::invalid_data)
+}
+
+pub(crate) fn state_to_string(state: State) -> String {
+    match state {
+        State::True => "True".to_string(), // Si usas "True" en el modelo
+        State::False => "False".to_string(), // Si usas "False" en el modelo
+        State::Value(s) => s, // Devuelve el string original ("Bueno", "Fuga", "bajo", etc.)
+    }
+}
+
+// --- 3. Implementación de Métodos Wasm ---
+
+#[wasm_bindgen(typescript_custom_section)]
+const INFERENCE_OPTIONS_TS: &'static str = r#"
+export interface InferenceOptions {
+    algorithm?: "likelihood_weighting" | "exact" | "junction_tree" | "gibbs";
+    samples?: number;
+    seed?: number;
+    max_time_ms?: number;
+    tolerance?: number;
+    chunk_size?: number;
+    burn_in?: number;
+    thinning?: number;
+    chains?: number;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const BN_ERROR_TS: &'static str = r#"
+export interface BnError {
+    kind: "UnknownNode" | "UnknownState" | "ImpossibleEvidence" | "InvalidModel" | "InvalidData" | "InvalidQuery" | "SamplingFailed" | "SerializationError";
+    message: string;
+    details: any;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const EM_OPTIONS_TS: &'static str = r#"
+export interface EmOptions {
+    max_iterations?: number;
+    tolerance?: number;
+    initialization?: { kind: "current" } | { kind: "uniform" } | { kind: "perturbed"; noise: number; seed: number };
+    prior?: { kind: "none" } | { kind: "uniform"; alpha: number } | { kind: "model"; weight: number };
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const STRUCTURE_OPTIONS_TS: &'static str = r#"
+export interface StructureOptions {
+    score?: { kind: "bic" } | { kind: "bdeu"; equivalent_sample_size: number };
+    search?: { kind: "hill_climbing" } | { kind: "tabu"; tenure: number; patience: number };
+    start?: "empty" | "current";
+    whitelist?: { from: string; to: string }[];
+    blacklist?: { from: string; to: string }[];
+    max_parents?: number;
+    max_iterations?: number;
+}
+"#;
+
+#[wasm_bindgen(typescript_custom_section)]
+const PC_OPTIONS_TS: &'static str = r#"
+export interface PcOptions {
+    test?: "chi_square" | "g_test";
+    alpha?: number;
+    max_conditioning?: number;
+}
+"#;
+
+#[wasm_bindgen]
+impl EmLearning {
+    /// Modelo con las CPTs ajustadas. Se entrega una sola vez; `trace()` sigue disponible.
+    #[wasm_bindgen]
+    pub fn model(&mut self) -> Result<BiodigestorModel, JsValue> {
+        self.model.take()
+            .ok_or_else(|| BnError::invalid_query("The EM model has already been taken").into())
+    }
+
+    /// `{ log_likelihood: number[], iterations, converged }`.
+    #[wasm_bindgen]
+    pub fn trace(&self) -> Result<JsValue, JsValue> {
+        self.trace.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+}
+
+#[wasm_bindgen]
+impl BiodigestorModel {
+
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once(); // Para ver errores en el navegador
+
+        let spec = default_model().map_err(BnError::invalid_model)?;
+        Ok(Self::from_spec(&spec)?)
+    }
+
+    /// Carga un modelo distinto del embebido desde su descripción JSON.
+    #[wasm_bindgen(js_name = "from_json")]
+    pub fn from_json_js(json: &str) -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once();
+        Self::from_json(json).map_err(JsValue::from)
+    }
+
+    /// Importa una red en formato Interchange BIF.
+    #[wasm_bindgen(js_name = "from_bif")]
+    pub fn from_bif_js(bif: &str) -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once();
+        Self::from_bif(bif).map_err(JsValue::from)
+    }
+
+    /// Importa una red en formato XMLBIF 0.3.
+    #[wasm_bindgen(js_name = "from_xmlbif")]
+    pub fn from_xmlbif_js(xml: &str) -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once();
+        Self::from_xmlbif(xml).map_err(JsValue::from)
+    }
+
+    /// Importa una red en formato .net de Hugin.
+    #[wasm_bindgen(js_name = "from_hugin")]
+    pub fn from_hugin_js(net: &str) -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once();
+        Self::from_hugin(net).map_err(JsValue::from)
+    }
+
+    /// Importa una red en formato .xdsl de GeNIe.
+    #[wasm_bindgen(js_name = "from_xdsl")]
+    pub fn from_xdsl_js(xdsl: &str) -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once();
+        Self::from_xdsl(xdsl).map_err(JsValue::from)
+    }
+
+    /// Exporta la red en uso como .net de Hugin.
+    #[wasm_bindgen]
+    pub fn export_hugin(&self) -> Result<String, JsValue> {
+        Ok(self.to_spec()?.to_hugin().map_err(BnError::invalid_model)?)
+    }
+
+    /// Exporta la red en uso como .xdsl de GeNIe.
+    #[wasm_bindgen]
+    pub fn export_xdsl(&self) -> Result<String, JsValue> {
+        Ok(self.to_spec()?.to_xdsl().map_err(BnError::invalid_model)?)
+    }
+
+    /// Exporta la red en uso como modelo JSON (el formato de `from_json`).
+    #[wasm_bindgen]
+    pub fn export_json(&self) -> Result<String, JsValue> {
+        Ok(self.to_spec()?.to_json().map_err(BnError::serialization)?)
+    }
+
+    /// Reestima las CPTs con una tabla de casos completos y devuelve un modelo nuevo.
+    /// `format` es `"csv"` o `"json"`; `prior_js` es opcional: `{ kind: "none" }`,
+    /// `{ kind: "uniform", alpha: 1 }` o `{ kind: "model", weight: 20 }`.
+    #[wasm_bindgen(js_name = "learn_parameters")]
+    pub fn learn_parameters_js(&self, data: &str, format: &str, prior_js: JsValue) -> Result<BiodigestorModel, JsValue> {
+        let data = parse_dataset(data, format)?;
+        let prior: Prior = if prior_js.is_undefined() || prior_js.is_null() {
+            Prior::default()
+        } else {
+            serde_wasm_bindgen::from_value(prior_js)
+                .map_err(|e| BnError::invalid_query(format!("Invalid prior: {}", e)))?
+        };
+
+        Ok(self.learn_parameters(&data, &prior)?)
+    }
+
+    /// Ajusta las CPTs por EM con casos en los que faltan nodos (p. ej. solo sensores).
+    /// `options_js` es opcional (ver `EmOptions`). Devuelve un `EmLearning`.
+    #[wasm_bindgen(js_name = "learn_em")]
+    pub fn learn_em_js(
+        &self,
+        data: &str,
+        format: &str,
+        #[wasm_bindgen(unchecked_param_type = "EmOptions | undefined")] options_js: JsValue,
+    ) -> Result<EmLearning, JsValue> {
+        let data = parse_dataset(data, format)?;
+        let options: EmOptions = if options_js.is_undefined() || options_js.is_null() {
+            EmOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| BnError::invalid_query(format!("Invalid EM options: {}", e)))?
+        };
+
+        let (model, trace) = self.learn_em(&data, &options)?;
+        Ok(EmLearning { model: Some(model), trace })
+    }
+
+    /// Busca la estructura que mejor explica una tabla de casos completos (ver
+    /// `StructureOptions`). Devuelve `{ variables, edges, score, initial_score,
+    /// iterations, diff: { added, removed, reversed, kept } }`.
+    #[wasm_bindgen(js_name = "learn_structure")]
+    pub fn learn_structure_js(
+        &self,
+        data: &str,
+        format: &str,
+        #[wasm_bindgen(unchecked_param_type = "StructureOptions | undefined")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let data = parse_dataset(data, format)?;
+        let options: StructureOptions = if options_js.is_undefined() || options_js.is_null() {
+            StructureOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| BnError::invalid_query(format!("Invalid structure options: {}", e)))?
+        };
+
+        let result = self.learn_structure(&data, &options)?;
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Algoritmo PC sobre una tabla de casos completos (ver `PcOptions`). Devuelve
+    /// `{ variables, directed, undirected, tests }`; cada test lleva `x`, `y`, `given`,
+    /// el estadístico, los grados de libertad, el p-valor y si acepta la independencia.
+    #[wasm_bindgen(js_name = "learn_pc")]
+    pub fn learn_pc_js(
+        &self,
+        data: &str,
+        format: &str,
+        #[wasm_bindgen(unchecked_param_type = "PcOptions | undefined")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let data = parse_dataset(data, format)?;
+        let options: PcOptions = if options_js.is_undefined() || options_js.is_null() {
+            PcOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| BnError::invalid_query(format!("Invalid PC options: {}", e)))?
+        };
+
+        let result = self.learn_pc(&data, &options)?;
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Fija la experiencia inicial (casos por fila) y el factor de olvido de `observe_case`.
+    #[wasm_bindgen(js_name = "set_adaptation")]
+    pub fn set_adaptation_js(&mut self, experience: f64, fading: f64) -> Result<(), JsValue> {
+        Ok(self.set_adaptation(experience, fading)?)
+    }
+
+    /// Actualiza las CPTs con un caso confirmado `{ nodo: estado }`; `weight` es 1 por
+    /// defecto. El modelo actualizado se guarda con `export_json` (o cualquier `export_*`).
+    #[wasm_bindgen(js_name = "observe_case")]
+    pub fn observe_case_js(&mut self, case_js: JsValue, weight: Option<f64>) -> Result<(), JsValue> {
+        let case: HashMap<String, String> = serde_wasm_bindgen::from_value(case_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid case format: {}", e)))?;
+        Ok(self.observe_case(&case, weight.unwrap_or(1.0))?)
+    }
+
+    /// Exporta la red en uso como Interchange BIF.
+    #[wasm_bindgen]
+    pub fn export_bif(&self) -> Result<String, JsValue> {
+        Ok(self.to_spec()?.to_bif().map_err(BnError::invalid_model)?)
+    }
+
+    /// Exporta la red en uso como XMLBIF 0.3.
+    #[wasm_bindgen]
+    pub fn export_xmlbif(&self) -> Result<String, JsValue> {
+        Ok(self.to_spec()?.to_xmlbif().map_err(BnError::invalid_model)?)
+    }
+
+    /// Igual que `from_json`, para archivos YAML.
+    #[wasm_bindgen(js_name = "from_yaml")]
+    pub fn from_yaml_js(yaml: &str) -> Result<BiodigestorModel, JsValue> {
+        console_error_panic_hook::set_once();
+        Self::from_yaml(yaml).map_err(JsValue::from)
+    }
+
+    /// Función de Inferencia. `options_js` es opcional (ver `InferenceOptions`).
+    /// Devuelve `{ algorithm, probabilities: { estado: prob }, sampling?, evidence }`,
+    /// con `evidence = { probability, log_probability, std_error? }` (P(evidencia)).
+    #[wasm_bindgen]
+    pub fn infer(
+        &self,
+        evidence_js: JsValue,
+        target_node: &str,
+        #[wasm_bindgen(unchecked_param_type = "InferenceOptions | undefined")] options_js: JsValue,
+    ) -> Result<JsValue, JsValue> {
+
+        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid evidence format: {}", e)))?;
+
+        let options: InferenceOptions = if options_js.is_undefined() || options_js.is_null() {
+            InferenceOptions::default()
+        } else {
+            serde_wasm_bindgen::from_value(options_js)
+                .map_err(|e| BnError::invalid_query(format!("Invalid inference options: {}", e)))?
+        };
+
+        let result = self.posterior(&evidence_map, target_node, &options)?;
+
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Marginales de todos los nodos: `{ nodo: { estado: prob } }` como objetos planos.
+    #[wasm_bindgen]
+    pub fn infer_all(&self, evidence_js: JsValue) -> Result<JsValue, JsValue> {
+        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid evidence format: {}", e)))?;
+
+        let result = self.posterior_all(&evidence_map)?;
+
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Explicación más probable de los nodos ocultos dada la evidencia.
+    /// `top_k` alternativas adicionales (por defecto 3).
+    #[wasm_bindgen]
+    pub fn most_probable_explanation(&self, evidence_js: JsValue, top_k: Option<usize>) -> Result<JsValue, JsValue> {
+        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid evidence format: {}", e)))?;
+
+        let result = self.mpe(&evidence_map, top_k.unwrap_or(3))?;
+
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Posterior conjunta de varios nodos, p. ej. `infer_joint(evidencia, ["EstadoMicrobiano", "EstadoOperativo"])`.
+    /// Con `given` es una consulta condicional: `infer_joint({}, ["Gas_sensor"], { EstadoMicrobiano: "Degradado" })`.
+    /// Devuelve `{ variables, given, entries: [{ states: [...], probability }] }`.
+    #[wasm_bindgen]
+    pub fn infer_joint(&self, evidence_js: JsValue, targets_js: JsValue, given_js: JsValue) -> Result<JsValue, JsValue> {
+        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid evidence format: {}", e)))?;
+        let targets: Vec<String> = serde_wasm_bindgen::from_value(targets_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid target list: {}", e)))?;
+        let given: HashMap<String, String> = if given_js.is_undefined() || given_js.is_null() {
+            HashMap::new()
+        } else {
+            serde_wasm_bindgen::from_value(given_js)
+                .map_err(|e| BnError::invalid_query(format!("Invalid conditions format: {}", e)))?
+        };
+
+        let result = self.joint_posterior(&evidence_map, &targets, &given)?;
+
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// MAP parcial sobre `variables` (array de nombres de nodo), p. ej.
+    /// `["EstadoMicrobiano", "EstadoOperativo"]`: distribución conjunta y su moda.
+    #[wasm_bindgen]
+    pub fn map_query(&self, evidence_js: JsValue, variables_js: JsValue) -> Result<JsValue, JsValue> {
+        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid evidence format: {}", e)))?;
+        let variables: Vec<String> = serde_wasm_bindgen::from_value(variables_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid variable list: {}", e)))?;
+
+        let result = self.partial_map(&evidence_map, &variables)?;
+
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// P(evidencia) exacta: `{ probability, log_probability }`. Un valor muy bajo señala
+    /// lecturas que no encajan con el modelo.
+    #[wasm_bindgen(js_name = "evidence_likelihood")]
+    pub fn evidence_likelihood_js(&self, evidence_js: JsValue) -> Result<JsValue, JsValue> {
+        let evidence_map: HashMap<String, String> = serde_wasm_bindgen::from_value(evidence_js)
+            .map_err(|e| BnError::invalid_query(format!("Invalid evidence format: {}", e)))?;
+
+        let result = self.evidence_likelihood(&evidence_map)?;
+
+        result.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Diagnósticos de la red en uso: `{ valid, diagnostics: [{ severity, node, kind, ... }] }`.
+    #[wasm_bindgen]
+    pub fn validate_model(&self) -> Result<JsValue, JsValue> {
+        let report = self.validate()?;
+
+        report.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Valida un archivo de modelo JSON sin cargarlo, para mostrar todos los problemas
+    /// que `from_json` rechazaría.
+    #[wasm_bindgen(js_name = "validate_json")]
+    pub fn validate_json_js(json: &str) -> Result<JsValue, JsValue> {
+        let report = ModelSpec::from_json(json).map_err(BnError::invalid_model)?.validate();
+
+        report.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    /// Nodos de la red: `[{ name, label, role, states }]`. `filter` es opcional:
+    /// `"observable"` (sensores), `"hidden"` (causas ocultas), `"target"` (consultables) o `"all"`.
+    #[wasm_bindgen]
+    pub fn get_node_names(&self, filter: Option<String>) -> Result<JsValue, JsValue> {
+        let filter = match filter.as_deref() {
+            None => NodeFilter::All,
+            Some(text) => NodeFilter::parse(text)
+                .ok_or_else(|| BnError::invalid_query(format!("Unknown node filter: {}", text)))?,
+        };
+        let nodes = self.node_list(filter);
+
+        nodes.serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    #[wasm_bindgen]
+    pub fn get_node_cpt(&self, node: &str) -> Result<JsValue, JsValue> {
+        let cpt = self.network.get_node_cpt_by_name(node)
+            .ok_or_else(|| BnError::unknown_node(node))?;
+
+        serde_wasm_bindgen::to_value(cpt)
+            .map_err(|e| BnError::serialization(e).into())
+    }
+
+    #[wasm_bindgen(js_name = "getGraphStructure")]
+    pub fn get_graph_structure(&self) -> Result<JsValue, JsValue> {
+        let graph_data = self.graph_structure();
 
         serde_wasm_bindgen::to_value(&graph_data)
-            .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+            .map_err(|e| BnError::serialization(e).into())
     }
 }
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> BiodigestorModel {
+        BiodigestorModel::from_spec(&default_model().expect("Failed to parse default model"))
+            .expect("Failed to build model")
+    }
+
+    #[test]
+    fn test_posterior_all_matches_single_queries() {
+        let model = model();
+        let evidence = HashMap::from([
+            ("T_sensor".to_string(), "baja".to_string()),
+            ("Gas_sensor".to_string(), "bajo".to_string()),
+        ]);
+
+        let all = model.posterior_all(&evidence).expect("posterior_all failed");
+        assert_eq!(all.len(), 12, "Debe haber una marginal por nodo");
+        assert!(all.contains_key("TemperaturaReal") && all.contains_key("ProduccionGasReal"));
+
+        let exact = InferenceOptions { algorithm: InferenceAlgorithm::Exact, ..InferenceOptions::default() };
+        for (node, marginal) in &all {
+            let single = model.posterior(&evidence, node, &exact).expect("posterior failed").probabilities;
+            for (state, p) in marginal {
+                assert!((p - single[state]).abs() < 1e-12, "{}={}: {} vs {}", node, state, p, single[state]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_mpe_ranks_hidden_assignments() {
+        let model = model();
+        let evidence = HashMap::from([
+            ("Flow_sensor".to_string(), "alto".to_string()),
+            ("Presion_sensor".to_string(), "baja".to_string()),
+        ]);
+
+        let mpe = model.mpe(&evidence, 4).expect("mpe failed");
+        assert_eq!(mpe.alternatives.len(), 4);
+        assert_eq!(mpe.best.assignment.len(), 10, "Solo deben aparecer los nodos ocultos");
+        assert!(!mpe.best.assignment.contains_key("Flow_sensor"));
+        assert_eq!(mpe.best.assignment["EstadoOperativo"], "Fuga");
+
+        let mut previous = mpe.best.probability;
+        for alternative in &mpe.alternatives {
+            assert!(alternative.probability <= previous);
+            previous = alternative.probability;
+        }
+        assert!(mpe.best.probability > 0.0 && mpe.best.probability <= 1.0);
+    }
+
+    #[test]
+    fn test_partial_map_joint_over_root_causes() {
+        let model = model();
+        let evidence = HashMap::from([("Gas_sensor".to_string(), "bajo".to_string())]);
+        let variables = vec!["EstadoMicrobiano".to_string(), "EstadoOperativo".to_string()];
+
+        let map = model.partial_map(&evidence, &variables).expect("map failed");
+        assert_eq!(map.distribution.len(), 6);
+        let total: f64 = map.distribution.iter().map(|e| e.probability).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        // Las marginales de la conjunta coinciden con la inferencia exacta de cada nodo
+        let options = InferenceOptions { algorithm: InferenceAlgorithm::Exact, ..InferenceOptions::default() };
+        let single = model.posterior(&evidence, "EstadoMicrobiano", &options).unwrap();
+        for (state, p) in &single.probabilities {
+            let marginal: f64 = map.distribution.iter()
+                .filter(|e| &e.assignment["EstadoMicrobiano"] == state)
+                .map(|e| e.probability)
+                .sum();
+            assert!((marginal - p).abs() < 1e-12, "{}: {} vs {}", state, marginal, p);
+        }
+
+        assert_eq!(map.best.assignment, map.distribution[0].assignment);
+        assert!(map.distribution.iter().all(|e| e.probability <= map.best.probability));
+        assert!(model.partial_map(&evidence, &[]).is_err());
+        assert!(model.partial_map(&evidence, &["NoExiste".to_string()]).is_err());
+    }
+
+    #[test]
+    fn test_joint_posterior_fault_matrix_and_conditional() {
+        let model = model();
+        let evidence = HashMap::from([("Flow_sensor".to_string(), "alto".to_string())]);
+        let targets = vec!["EstadoOperativo".to_string(), "EstadoMicrobiano".to_string()];
+
+        let joint = model.joint_posterior(&evidence, &targets, &HashMap::new()).expect("joint failed");
+        assert_eq!(joint.entries.len(), 6);
+        // Orden de `targets`, con el último variando más rápido
+        assert_eq!(joint.entries[0].states, vec!["Normal", "Bueno"]);
+        assert_eq!(joint.entries[1].states, vec!["Normal", "Degradado"]);
+        let total: f64 = joint.entries.iter().map(|e| e.probability).sum();
+        assert!((total - 1.0).abs() < 1e-12);
+
+        // Consulta condicional = consulta con la condición como evidencia
+        let given = HashMap::from([("EstadoMicrobiano".to_string(), "Degradado".to_string())]);
+        let conditional = model.joint_posterior(&HashMap::new(), &["Gas_sensor".to_string()], &given).unwrap();
+        let options = InferenceOptions { algorithm: InferenceAlgorithm::Exact, ..InferenceOptions::default() };
+        let single = model.posterior(&given, "Gas_sensor", &options).unwrap();
+        for entry in &conditional.entries {
+            assert!((entry.probability - single.probabilities[&entry.states[0]]).abs() < 1e-12);
+        }
+
+        let contradiction = HashMap::from([("Flow_sensor".to_string(), "bajo".to_string())]);
+        assert!(model.joint_posterior(&evidence, &targets, &contradiction).is_err());
+    }
+
+    #[test]
+    fn test_from_json_keeps_metadata() {
+        let model = model();
+        assert_eq!(model.node_metadata("EstadoMicrobiano").and_then(|m| m.label.as_deref()), Some("Estado microbiano"));
+        assert!(BiodigestorModel::from_json("{ \"name\": \"roto\" }").is_err());
+    }
+
+    #[test]
+    fn test_unknown_evidence_state_is_rejected() {
+        let model = model();
+        let evidence = HashMap::from([("T_sensor".to_string(), "Normal".to_string())]);
+
+        let error = model.check_evidence(&evidence).unwrap_err();
+        assert_eq!((error.node.as_str(), error.suggestion.as_deref()), ("T_sensor", Some("normal")));
+        assert_eq!(error.valid, vec!["baja", "normal", "alta"]);
+
+        let options = InferenceOptions { algorithm: InferenceAlgorithm::Exact, ..InferenceOptions::default() };
+        let error = model.posterior(&evidence, "EstadoMicrobiano", &options).unwrap_err();
+        assert_eq!(error.kind(), "UnknownState");
+        assert!(error.to_string().contains("did you mean 'normal'"), "{}", error);
+        assert!(model.posterior_all(&evidence).is_err());
+        assert!(model.mpe(&evidence, 1).is_err());
+    }
+
+    #[test]
+    fn test_errors_are_typed() {
+        let model = model();
+        let evidence = HashMap::from([("Fuga".to_string(), "si".to_string())]);
+        assert_eq!(model.posterior_all(&evidence).unwrap_err(), BnError::unknown_node("Fuga"));
+
+        let no_variables = model.partial_map(&HashMap::new(), &[]).unwrap_err();
+        assert_eq!(no_variables.kind(), "InvalidQuery");
+        assert_eq!(BiodigestorModel::from_bif("network {").err().map(|e| e.kind()), Some("InvalidModel"));
+    }
+
+    #[test]
+    fn test_sampling_without_valid_samples_is_an_error() {
+        let model = model();
+        let evidence = HashMap::from([("T_sensor".to_string(), "alta".to_string())]);
+        let none = InferenceOptions { samples: 0, ..InferenceOptions::default() };
+        assert_eq!(model.posterior(&evidence, "EstadoMicrobiano", &none).err().map(|e| e.kind()), Some("InvalidQuery"));
+
+        // P(baja) = 1e-6: con 10 muestras ninguna tiene peso positivo
+        let rare = BiodigestorModel::from_spec(&learning::fuga_spec(0.000001, 1.0, 0.0)).unwrap();
+        let evidence = HashMap::from([("Presion".to_string(), "baja".to_string())]);
+        let options = InferenceOptions { samples: 10, seed: Some(1), ..InferenceOptions::default() };
+        assert_eq!(rare.posterior(&evidence, "Fuga", &options).err().map(|e| e.kind()), Some("SamplingFailed"));
+    }
+
+    #[test]
+    fn test_every_result_reports_evidence_likelihood() {
+        let model = model();
+        let evidence = HashMap::from([
+            ("T_sensor".to_string(), "alta".to_string()),
+            ("Gas_sensor".to_string(), "bajo".to_string()),
+        ]);
+        let exact = model.evidence_likelihood(&evidence).unwrap();
+        assert!(exact.probability > 0.0 && exact.probability < 1.0);
+        assert!((exact.log_probability - exact.probability.ln()).abs() < 1e-12);
+        assert!((model.evidence_likelihood(&HashMap::new()).unwrap().probability - 1.0).abs() < 1e-12);
+
+        let options = |algorithm| InferenceOptions { algorithm, seed: Some(3), samples: 20_000, ..InferenceOptions::default() };
+        let junction = model.posterior(&evidence, "EstadoMicrobiano", &options(InferenceAlgorithm::JunctionTree)).unwrap();
+        assert!((junction.evidence.probability - exact.probability).abs() < 1e-12);
+        assert_eq!(junction.evidence.std_error, None);
+
+        // La estimación por muestreo debe caer cerca del valor exacto
+        let sampled = model.posterior(&evidence, "EstadoMicrobiano", &options(InferenceAlgorithm::LikelihoodWeighting)).unwrap();
+        let std_error = sampled.evidence.std_error.expect("Sampled results estimate P(e)");
+        assert!((sampled.evidence.probability - exact.probability).abs() < 4.0 * std_error, "{:?} vs {:?}", sampled.evidence, exact);
+
+        let mpe = model.mpe(&evidence, 0).unwrap();
+        let map = model.partial_map(&evidence, &["EstadoOperativo".to_string()]).unwrap();
+        for reported in [mpe.evidence, map.evidence] {
+            assert!((reported.probability - exact.probability).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_impossible_evidence_names_the_conflict() {
+        // Una fuga siempre baja la presión; la temperatura no interviene en el conflicto
+        let yaml = r#"
+name: fuga
+nodes:
+  - name: Fuga
+    states: [si, no]
+    cpt: [ { given: [], probabilities: { si: 0.1, no: 0.9 } } ]
+  - name: Presion
+    states: [baja, alta]
+    parents: [Fuga]
+    cpt:
+      - { given: [si], probabilities: { baja: 1.0, alta: 0.0 } }
+      - { given: [no], probabilities: { baja: 0.2, alta: 0.8 } }
+  - name: Temperatura
+    states: [fria, caliente]
+    parents: [Fuga]
+    cpt:
+      - { given: [si], probabilities: { fria: 0.7, caliente: 0.3 } }
+      - { given: [no], probabilities: { fria: 0.4, caliente: 0.6 } }
+"#;
+        let model = BiodigestorModel::from_yaml(yaml).unwrap();
+        let evidence = HashMap::from([
+            ("Fuga".to_string(), "si".to_string()),
+            ("Presion".to_string(), "alta".to_string()),
+            ("Temperatura".to_string(), "fria".to_string()),
+        ]);
+        let expected = BTreeMap::from([
+            ("Fuga".to_string(), "si".to_string()),
+            ("Presion".to_string(), "alta".to_string()),
+        ]);
+
+        for algorithm in [InferenceAlgorithm::LikelihoodWeighting, InferenceAlgorithm::Gibbs, InferenceAlgorithm::Exact] {
+            let options = InferenceOptions { algorithm, ..InferenceOptions::default() };
+            let error = model.posterior(&evidence, "Temperatura", &options).unwrap_err();
+            let BnError::ImpossibleEvidence { conflict, evidence: all } = &error else { panic!("{:?}", error) };
+            assert_eq!(conflict, &expected);
+            assert_eq!(all.len(), 3);
+            assert!(error.to_string().contains("Fuga=si, Presion=alta"), "{}", error);
+        }
+        assert_eq!(model.posterior_all(&evidence).unwrap_err().kind(), "ImpossibleEvidence");
+        assert_eq!(model.mpe(&evidence, 1).unwrap_err().kind(), "ImpossibleEvidence");
+
+        let possible = HashMap::from([("Fuga".to_string(), "no".to_string()), ("Presion".to_string(), "alta".to_string())]);
+        assert!(model.posterior_all(&possible).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_model_is_refused() {
+        assert!(model().validate().unwrap().valid);
+
+        let mut spec = default_model().unwrap();
+        let gas = spec.nodes.iter_mut().find(|n| n.name == "ProduccionGasReal").unwrap();
+        gas.cpt.pop();
+        let error = BiodigestorModel::from_spec(&spec).err().expect("A model with a missing row must be refused");
+        let BnError::InvalidModel { message, diagnostics } = error else { panic!("Expected InvalidModel") };
+        assert!(message.contains("ProduccionGasReal") && message.contains("Degradado"), "{}", message);
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_live_network_exports_to_bif() {
+        let model = model();
+        let bif = model.to_spec().unwrap().to_bif().expect("export failed");
+        let reloaded = BiodigestorModel::from_bif(&bif).expect("import failed");
+
+        let evidence = HashMap::from([("Gas_sensor".to_string(), "bajo".to_string())]);
+        let options = InferenceOptions { algorithm: InferenceAlgorithm::Exact, ..InferenceOptions::default() };
+        let before = model.posterior(&evidence, "EstadoMicrobiano", &options).unwrap();
+        let after = reloaded.posterior(&evidence, "EstadoMicrobiano", &options).unwrap();
+        assert_eq!(before.probabilities, after.probabilities);
+        assert_eq!(reloaded.node_metadata("CaudalReal"), model.node_metadata("CaudalReal"));
+    }
+
+    #[test]
+    fn test_imported_positions_reach_the_graph() {
+        let net = r#"
+            net { }
+            node A { states = ("si" "no"); position = (10 20); }
+            node B { states = ("si" "no"); }
+            potential (A) { data = (0.3 0.7); }
+            potential (B | A) { data = ((0.9 0.1) (0.2 0.8)); }
+        "#;
+        let model = BiodigestorModel::from_hugin(net).expect("import failed");
+        let graph = model.graph_structure();
+
+        let a = graph.nodes.iter().find(|n| n.id == "A").unwrap();
+        assert_eq!((a.x, a.y), (Some(10.0), Some(20.0)));
+        let b = graph.nodes.iter().find(|n| n.id == "B").unwrap();
+        assert_eq!((b.x, b.y), (None, None));
+        assert_eq!(graph.edges.len(), 1);
+    }
+
+    #[test]
+    fn test_node_list_comes_from_the_network() {
+        let model = model();
+        let all = model.node_list(NodeFilter::All);
+        assert_eq!(all.len(), 12);
+        assert!(all.iter().any(|n| n.name == "pHReal") && all.iter().any(|n| n.name == "ProduccionGasReal"));
+
+        let sensors = model.node_list(NodeFilter::Observable);
+        assert_eq!(sensors.len(), 5);
+        assert!(sensors.iter().all(|n| n.name.ends_with("_sensor") && n.role == NodeRole::Sensor));
+
+        let hidden = model.node_list(NodeFilter::Hidden);
+        assert_eq!(hidden.iter().map(|n| n.name.as_str()).collect::<Vec<_>>(), vec!["EstadoMicrobiano", "EstadoOperativo"]);
+        assert_eq!(hidden[1].states, vec!["Normal", "FallaMecanica", "Fuga"]);
+        assert_eq!(model.node_list(NodeFilter::Target).len(), 7);
+        assert_eq!(NodeFilter::parse("sensores"), None);
+    }
+
+    #[test]
+    fn test_learned_model_replaces_expert_cpts() {
+        let model = model();
+        let mut csv = String::from("EstadoMicrobiano,T_sensor,TemperaturaReal\n");
+        for _ in 0..30 {
+            csv.push_str("Degradado,baja,Baja\nBueno,normal,Normal\n");
+        }
+        let data = Dataset::from_csv(&csv).unwrap();
+        let learned = model.learn_parameters(&data, &Prior::Model { weight: 10.0 }).unwrap();
+
+        let evidence = HashMap::new();
+        let before = model.posterior_all(&evidence).unwrap()["EstadoMicrobiano"]["Degradado"];
+        let after = learned.posterior_all(&evidence).unwrap()["EstadoMicrobiano"]["Degradado"];
+        // 30 de 60 casos degradados frente al 0.15 experto (con 10 casos de prior)
+        assert!((after - (30.0 + 1.5) / 70.0).abs() < 1e-12, "{} -> {}", before, after);
+        assert_eq!(learned.node_metadata("EstadoMicrobiano"), model.node_metadata("EstadoMicrobiano"));
+
+        let reloaded = BiodigestorModel::from_json(&learned.to_spec().unwrap().to_json().unwrap()).unwrap();
+        assert_eq!(reloaded.posterior_all(&evidence).unwrap()["EstadoMicrobiano"]["Degradado"], after);
+
+        let typo = Dataset::from_csv("T_sensor\nNormal\n").unwrap();
+        assert_eq!(model.learn_parameters(&typo, &Prior::None).err().map(|e| e.kind()), Some("InvalidData"));
+    }
+
+    #[test]
+    fn test_observe_case_updates_the_model_in_place() {
+        let mut model = model();
+        let no_evidence = HashMap::new();
+        let before = model.posterior_all(&no_evidence).unwrap()["EstadoOperativo"]["Fuga"];
+
+        let case: HashMap<String, String> = [("EstadoOperativo", "Fuga"), ("Presion_sensor", "baja")]
+            .iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        model.set_adaptation(10.0, 1.0).unwrap();
+        model.observe_case(&case, 2.0).unwrap();
+        let after = model.posterior_all(&no_evidence).unwrap()["EstadoOperativo"]["Fuga"];
+        // Dos casos de fuga sobre 10 de experiencia
+        assert!((after - (10.0 * before + 2.0) / 12.0).abs() < 1e-9, "{} -> {}", before, after);
+        assert!(model.node_metadata("EstadoOperativo").is_some());
+
+        let reloaded = BiodigestorModel::from_json(&model.to_spec().unwrap().to_json().unwrap()).unwrap();
+        assert!((reloaded.posterior_all(&no_evidence).unwrap()["EstadoOperativo"]["Fuga"] - after).abs() < 1e-12);
+
+        let typo: HashMap<String, String> = [("EstadoOperativo".to_string(), "fuga".to_string())].into();
+        assert_eq!(model.observe_case(&typo, 1.0).err().map(|e| e.kind()), Some("UnknownState"));
+        assert_eq!(model.observe_case(&case, 0.0).err().map(|e| e.kind()), Some("InvalidQuery"));
+        assert_eq!(model.set_adaptation(10.0, 1.5).err().map(|e| e.kind()), Some("InvalidQuery"));
+    }
+
+    #[test]
+    fn test_structure_diff_against_the_network() {
+        let model = model();
+        let data = learning::sample_dataset(&model.to_spec().unwrap(), 500, 3);
+        let options = StructureOptions {
+            whitelist: vec![learning::Edge::new("CaudalReal", "pHReal")],
+            max_iterations: 20,
+            ..StructureOptions::default()
+        };
+        let result = model.learn_structure(&data, &options).unwrap();
+        assert_eq!(result.variables.len(), 12);
+        assert!(result.diff.added.contains(&learning::Edge::new("CaudalReal", "pHReal")));
+        assert!(result.score >= result.initial_score);
+
+        let unknown = StructureOptions { blacklist: vec![learning::Edge::new("pH", "CaudalReal")], ..StructureOptions::default() };
+        assert_eq!(model.learn_structure(&data, &unknown).err().map(|e| e.kind()), Some("InvalidQuery"));
+        let incomplete = Dataset::from_csv("T_sensor,pH_sensor\nalta,\n").unwrap();
+        assert_eq!(model.learn_structure(&incomplete, &StructureOptions::default()).err().map(|e| e.kind()), Some("InvalidData"));
+    }
+
+    #[test]
+    fn test_pc_reports_sensor_dependencies() {
+        let model = model();
+        let data = learning::sample_dataset(&model.to_spec().unwrap(), 1000, 8);
+        let options = PcOptions { max_conditioning: Some(1), ..PcOptions::default() };
+        let result = model.learn_pc(&data, &options).unwrap();
+        assert_eq!(result.variables.len(), 12);
+        assert!(!result.tests.is_empty());
+        // Cada sensor sigue unido a su magnitud real
+        let linked = |a: &str, b: &str| result.directed.iter().chain(&result.undirected)
+            .any(|e| (e.from == a && e.to == b) || (e.from == b && e.to == a));
+        assert!(linked("TemperaturaReal", "T_sensor") && linked("PresionReal", "Presion_sensor"));
+
+        let bad = PcOptions { alpha: 0.0, ..options };
+        assert_eq!(model.learn_pc(&data, &bad).err().map(|e| e.kind()), Some("InvalidQuery"));
+    }
+
+    #[test]
+    fn test_em_learns_from_sensor_logs() {
+        let model = model();
+        let mut csv = String::from("T_sensor,pH_sensor,Gas_sensor\n");
+        for _ in 0..20 {
+            csv.push_str("alta,acido,bajo\nnormal,neutro,normal\n");
+        }
+        let data = Dataset::from_csv(&csv).unwrap();
+        let options = EmOptions { max_iterations: 10, ..EmOptions::default() };
+        let (learned, trace) = model.learn_em(&data, &options).unwrap();
+        assert_eq!(trace.log_likelihood.len(), trace.iterations + 1);
+
+        let evidence = HashMap::new();
+        let before = model.posterior_all(&evidence).unwrap()["EstadoMicrobiano"]["Degradado"];
+        let after = learned.posterior_all(&evidence).unwrap()["EstadoMicrobiano"]["Degradado"];
+        // La mitad de los registros apuntan a un proceso degradado
+        assert!(after > before, "{} -> {}", before, after);
+
+        let bad = EmOptions { tolerance: -1.0, ..options };
+        assert_eq!(model.learn_em(&data, &bad).err().map(|e| e.kind()), Some("InvalidQuery"));
+    }
+
+    #[test]
+    fn test_graph_groups_come_from_metadata() {
+        let graph = model().graph_structure();
+        let node = |id: &str| graph.nodes.iter().find(|n| n.id == id).unwrap();
+        assert_eq!((node("EstadoMicrobiano").group.as_str(), node("EstadoMicrobiano").label.as_str()), ("Oculto", "Estado microbiano"));
+        assert_eq!(node("pHReal").group, "Físico");
+        assert_eq!((node("Gas_sensor").role, node("CaudalReal").units.as_deref()), (NodeRole::Sensor, Some("m³/h")));
+        assert!(node("EstadoOperativo").state_descriptions.contains_key("Fuga"));
+
+        // Sin papel declarado se deduce de la estructura, no del nombre
+        let net = r#"
+            net { }
+            node SensorFalso { states = ("si" "no"); role = "hidden"; }
+            node Lectura { states = ("si" "no"); }
+            potential (SensorFalso) { data = (0.3 0.7); }
+            potential (Lectura | SensorFalso) { data = ((0.9 0.1) (0.2 0.8)); }
+        "#;
+        let model = BiodigestorModel::from_hugin(net).unwrap();
+        assert_eq!(model.node_role("SensorFalso"), Some(NodeRole::Hidden));
+        assert_eq!(model.node_role("Lectura"), Some(NodeRole::Sensor));
+        assert_eq!(model.node_role("Otro"), None);
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use suma_core::core::probability::bayes::BayesianNetwork;
+
+use crate::inference::factor::increment;
+use crate::inference::tables::NetworkTables;
+use crate::state_to_string;
+
+// Formato declarativo del modelo (JSON o YAML). Ejemplo mínimo:
+//
+// {
+//   "name": "Biodigestor",
+//   "nodes": [
+//     { "name": "EstadoMicrobiano", "states": ["Bueno", "Degradado"],
+//       "cpt": [ { "given": [], "probabilities": { "Bueno": 0.85, "Degradado": 0.15 } } ] },
+//     { "name": "pHReal", "label": "pH real", "states": ["Acido", "Neutro", "Alcalino"],
+//       "parents": ["EstadoMicrobiano"],
+//       "cpt": [ { "given": ["Bueno"], "probabilities": { "Acido": 0.1, "Neutro": 0.85, "Alcalino": 0.05 } },
+//                { "given": ["Degradado"], "probabilities": { "Acido": 0.6, "Neutro": 0.3, "Alcalino": 0.1 } } ] }
+//   ]
+// }
+//
+// `given` lista un estado por padre, en el orden de `parents`. Los nodos pueden
+// aparecer en cualquier orden: se insertan en la red en orden topológico.
+
+/// Modelo por defecto, embebido en el binario.
+pub(crate) const DEFAULT_MODEL_JSON: &str = include_str!("../models/biodigestor.json");
+
+/// Descripción completa de una red: nodos, estados, padres, CPTs y metadatos.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub nodes: Vec<NodeSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeSpec {
+    pub name: String,
+    pub states: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parents: Vec<String>,
+    pub cpt: Vec<CptRow>,
+    #[serde(flatten)]
+    pub metadata: NodeMetadata,
+}
+
+/// Una fila de la CPT: distribución del nodo para una combinación de estados de los padres.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CptRow {
+    #[serde(default)]
+    pub given: Vec<String>,
+    pub probabilities: BTreeMap<String, f64>,
+}
+
+/// Papel del nodo en el diagnóstico.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    /// Causa no observable (estado microbiano, averías...).
+    Hidden,
+    /// Magnitud física real, medida por un sensor.
+    Physical,
+    /// Lectura de un sensor: lo que se introduce como evidencia.
+    Sensor,
+}
+
+impl NodeRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NodeRole::Hidden => "hidden",
+            NodeRole::Physical => "physical",
+            NodeRole::Sensor => "sensor",
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<NodeRole> {
+        match text.trim() {
+            "hidden" => Some(NodeRole::Hidden),
+            "physical" => Some(NodeRole::Physical),
+            "sensor" => Some(NodeRole::Sensor),
+            _ => None,
+        }
+    }
+}
+
+/// Metadatos de presentación; no intervienen en la inferencia.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodeMetadata {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<NodeRole>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub units: Option<String>,
+    /// Posición `[x, y]` del nodo en el diagrama (y crece hacia abajo).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<[f64; 2]>,
+    /// Descripción de cada estado, por nombre de estado.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub state_descriptions: BTreeMap<String, String>,
+}
+
+impl ModelSpec {
+    pub fn from_json(json: &str) -> Result<ModelSpec, String> {
+        serde_json::from_str(json).map_err(|e| format!("Invalid model JSON: {}", e))
+    }
+
+    pub fn from_yaml(yaml: &str) -> Result<ModelSpec, String> {
+        serde_yaml::from_str(yaml).map_err(|e| format!("Invalid model YAML: {}", e))
+    }
+
+    /// JSON con sangría, en el mismo formato que lee `from_json`.
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Cannot write model JSON: {}", e))
+    }
+
+    /// Construye la `BayesianNetwork` con `add_discrete_node`.
+    pub fn build_network(&self) -> Result<BayesianNetwork, String> {
+        let mut bn = BayesianNetwork::new();
+        for node in self.insertion_order()? {
+            if node.cpt.iter().any(|row| row.given.len() != node.parents.len()) {
+                return Err(format!("Node '{}': every CPT row needs one state per parent", node.name));
+            }
+
+            // Los motores de inferencia leen las filas con los padres ordenados por id;
+            // se reordenan aquí para que el orden de `parents` en el archivo sea libre.
+            let mut order: Vec<usize> = (0..node.parents.len()).collect();
+            order.sort_by_key(|&i| bn.get_id_from_name(&node.parents[i]));
+
+            let parents: Vec<&str> = order.iter().map(|&i| node.parents[i].as_str()).collect();
+            let states: Vec<&str> = node.states.iter().map(String::as_str).collect();
+            let table: HashMap<Vec<&str>, HashMap<&str, f64>> = node.cpt.iter()
+                .map(|row| {
+                    let given = order.iter().map(|&i| row.given[i].as_str()).collect();
+                    let probabilities = row.probabilities.iter().map(|(s, p)| (s.as_str(), *p)).collect();
+                    (given, probabilities)
+                })
+                .collect();
+
+            bn.add_discrete_node(&node.name, parents, states, table)
+                .map_err(|e| format!("Node '{}': {}", node.name, e))?;
+        }
+        Ok(bn)
+    }
+
+    /// Describe una red ya construida: nodos en orden topológico, padres ordenados por id
+    /// y filas de la CPT con el último padre variando más rápido. Las filas ausentes
+    /// de la CPT original se omiten.
+    pub fn from_network(network: &BayesianNetwork, name: &str) -> Result<ModelSpec, String> {
+        let tables = NetworkTables::from_network(network)?;
+        let nodes = tables.topological_order().into_iter()
+            .map(|index| {
+                let node = &tables.nodes()[index];
+                let states: Vec<String> = node.states.iter().cloned().map(state_to_string).collect();
+                let parents: Vec<String> = node.parents.iter().map(|p| tables.nodes()[*p].name.clone()).collect();
+                let parent_cards: Vec<usize> = node.parents.iter().map(|p| tables.card(*p)).collect();
+                let rows: usize = parent_cards.iter().product();
+
+                let mut cpt = Vec::with_capacity(rows);
+                let mut assignment = vec![0; parent_cards.len()];
+                for row in 0..rows {
+                    let values = tables.cpt_row(index, row);
+                    if values.iter().any(|p| *p != 0.0) {
+                        let given = node.parents.iter().zip(&assignment)
+                            .map(|(p, s)| state_to_string(tables.nodes()[*p].states[*s].clone()))
+                            .collect();
+                        let probabilities = states.iter().cloned().zip(values.iter().copied()).collect();
+                        cpt.push(CptRow { given, probabilities });
+                    }
+                    increment(&mut assignment, &parent_cards);
+                }
+
+                NodeSpec { name: node.name.clone(), states, parents, cpt, metadata: NodeMetadata::default() }
+            })
+            .collect();
+
+        Ok(ModelSpec { name: name.to_string(), description: None, nodes })
+    }
+
+    pub fn metadata(&self) -> HashMap<String, NodeMetadata> {
+        self.nodes.iter().map(|n| (n.name.clone(), n.metadata.clone())).collect()
+    }
+
+    /// Orden del archivo, salvo que un nodo aparezca antes que alguno de sus padres.
+    pub(crate) fn insertion_order(&self) -> Result<Vec<&NodeSpec>, String> {
+        let mut placed: Vec<&NodeSpec> = Vec::with_capacity(self.nodes.len());
+        let mut pending: Vec<&NodeSpec> = self.nodes.iter().collect();
+        while !pending.is_empty() {
+            let before = pending.len();
+            pending.retain(|node| {
+                let ready = node.parents.iter().all(|p| placed.iter().any(|n| &n.name == p));
+                if ready {
+                    placed.push(node);
+                }
+                !ready
+            });
+            if pending.len() == before {
+                let names: Vec<&str> = pending.iter().map(|n| n.name.as_str()).collect();
+                return Err(format!("Unknown parent or cycle involving: {}", names.join(", ")));
+            }
+        }
+        Ok(placed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use suma_core::core::probability::bayes::BN_base::BayesianNetworkBase;
+
+    #[test]
+    fn test_default_model_builds() {
+        let spec = ModelSpec::from_json(DEFAULT_MODEL_JSON).expect("Default model must parse");
+        let bn = spec.build_network().expect("Default model must build");
+        assert_eq!(bn.get_nodes().len(), 12);
+        assert_eq!(spec.metadata()["CaudalReal"].units.as_deref(), Some("m³/h"));
+    }
+
+    #[test]
+    fn test_parent_order_and_node_order_are_free() {
+        let yaml = r#"
+name: Orden
+nodes:
+  - name: C
+    states: [si, no]
+    parents: [B, A]
+    cpt:
+      - { given: [x, u], probabilities: { si: 0.1, "no": 0.9 } }
+      - { given: [x, v], probabilities: { si: 0.2, "no": 0.8 } }
+      - { given: [y, u], probabilities: { si: 0.3, "no": 0.7 } }
+      - { given: [y, v], probabilities: { si: 0.4, "no": 0.6 } }
+  - name: A
+    states: [u, v]
+    cpt: [ { given: [], probabilities: { u: 0.5, v: 0.5 } } ]
+  - name: B
+    states: [x, y]
+    cpt: [ { given: [], probabilities: { x: 0.5, y: 0.5 } } ]
+"#;
+        let spec = ModelSpec::from_yaml(yaml).expect("YAML must parse");
+        let bn = spec.build_network().expect("Model must build");
+        let tables = crate::inference::tables::NetworkTables::from_network(&bn).unwrap();
+
+        // A=v, B=x → fila del archivo [x, v] → P(si) = 0.2
+        let index = |name: &str| tables.index_of(bn.get_id_from_name(name).unwrap()).unwrap();
+        let mut assignment = vec![0; tables.len()];
+        assignment[index("A")] = 1;
+        assignment[index("B")] = 0;
+        let c = index("C");
+        assert!((tables.cpt_row(c, tables.parent_row(c, &assignment))[0] - 0.2).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_rejects_cycles_and_bad_rows() {
+        let cycle = r#"{ "name": "x", "nodes": [
+            { "name": "A", "states": ["s"], "parents": ["B"], "cpt": [ { "given": ["s"], "probabilities": { "s": 1.0 } } ] },
+            { "name": "B", "states": ["s"], "parents": ["A"], "cpt": [ { "given": ["s"], "probabilities": { "s": 1.0 } } ] } ] }"#;
+        assert!(ModelSpec::from_json(cycle).unwrap().build_network().is_err());
+
+        let short_row = r#"{ "name": "x", "nodes": [
+            { "name": "A", "states": ["s"], "cpt": [ { "given": [], "probabilities": { "s": 1.0 } } ] },
+            { "name": "B", "states": ["s"], "parents": ["A"], "cpt": [ { "given": [], "probabilities": { "s": 1.0 } } ] } ] }"#;
+        assert!(ModelSpec::from_json(short_row).unwrap().build_network().is_err());
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use serde::Serialize;
+
+use crate::error::BnError;
+use crate::formats::parent_configurations;
+use crate::model::ModelSpec;
+
+// Validación estructural y numérica de un modelo antes de usarlo. `add_discrete_node`
+// solo comprueba la suma de cada fila; aquí se revisa el modelo completo y se
+// devuelven todos los hallazgos, no solo el primero.
+
+/// Tolerancia para la suma de cada fila de una CPT (la misma que `add_discrete_node`).
+pub const ROW_SUM_TOLERANCE: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// El modelo no puede usarse para inferencia.
+    Error,
+    /// Sospechoso, pero la inferencia sigue siendo correcta.
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Issue {
+    /// La fila no suma 1 dentro de `ROW_SUM_TOLERANCE`.
+    RowSum { given: Vec<String>, sum: f64 },
+    /// Probabilidad negativa, mayor que 1 o no finita.
+    InvalidProbability { given: Vec<String>, state: String, value: f64 },
+    /// Combinación de estados de los padres sin fila en la CPT.
+    MissingRow { given: Vec<String> },
+    /// Dos filas para la misma combinación de padres.
+    DuplicateRow { given: Vec<String> },
+    /// La fila no tiene un estado por padre.
+    RowArity { given: Vec<String>, expected: usize },
+    /// Estado usado en una fila que no está declarado (en el nodo o en el padre indicado).
+    UndeclaredState { given: Vec<String>, state: String, declared_in: String },
+    UnknownParent { parent: String },
+    DuplicateNode,
+    NoStates,
+    /// Ciclo dirigido, listado en orden de recorrido.
+    Cycle { nodes: Vec<String> },
+    /// Nodo sin padres ni hijos.
+    Orphan,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub node: String,
+    #[serde(flatten)]
+    pub issue: Issue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let node = &self.node;
+        match &self.issue {
+            Issue::RowSum { given, sum } => write!(f, "{}: row {:?} sums to {}", node, given, sum),
+            Issue::InvalidProbability { given, state, value } => {
+                write!(f, "{}: P({} | {:?}) = {} is not a probability", node, state, given, value)
+            }
+            Issue::MissingRow { given } => write!(f, "{}: no row for parent states {:?}", node, given),
+            Issue::DuplicateRow { given } => write!(f, "{}: more than one row for parent states {:?}", node, given),
+            Issue::RowArity { given, expected } => {
+                write!(f, "{}: row {:?} has {} parent states, expected {}", node, given, given.len(), expected)
+            }
+            Issue::UndeclaredState { given, state, declared_in } => {
+                write!(f, "{}: row {:?} uses state '{}' not declared by {}", node, given, state, declared_in)
+            }
+            Issue::UnknownParent { parent } => write!(f, "{}: unknown parent '{}'", node, parent),
+            Issue::DuplicateNode => write!(f, "{}: node declared more than once", node),
+            Issue::NoStates => write!(f, "{}: node declares no states", node),
+            Issue::Cycle { nodes } => write!(f, "{}: cycle {}", node, nodes.join(" -> ")),
+            Issue::Orphan => write!(f, "{}: node has no parents and no children", node),
+        }
+    }
+}
+
+/// Resultado de `validate`: `valid` es falso si hay al menos un error.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics.iter().filter(|d| d.severity == Severity::Error)
+    }
+
+    /// `BnError::InvalidModel` con todos los diagnósticos si hay algún error.
+    pub fn into_result(self) -> Result<(), BnError> {
+        if self.valid {
+            return Ok(());
+        }
+        let errors: Vec<String> = self.errors().map(|d| d.to_string()).collect();
+        let message = format!("Invalid model: {}", errors.join("; "));
+        Err(BnError::InvalidModel { message, diagnostics: self.diagnostics })
+    }
+}
+
+impl ModelSpec {
+    pub fn validate(&self) -> ValidationReport {
+        let mut diagnostics = Vec::new();
+        let mut push = |severity, node: &str, issue| diagnostics.push(Diagnostic { severity, node: node.to_string(), issue });
+
+        let mut seen = BTreeSet::new();
+        for node in &self.nodes {
+            if !seen.insert(node.name.as_str()) {
+                push(Severity::Error, &node.name, Issue::DuplicateNode);
+            }
+            if node.states.is_empty() {
+                push(Severity::Error, &node.name, Issue::NoStates);
+            }
+        }
+
+        let by_name: HashMap<&str, usize> = self.nodes.iter().enumerate().map(|(i, n)| (n.name.as_str(), i)).collect();
+        for node in &self.nodes {
+            let unknown: Vec<&String> = node.parents.iter().filter(|p| !by_name.contains_key(p.as_str())).collect();
+            for parent in &unknown {
+                push(Severity::Error, &node.name, Issue::UnknownParent { parent: parent.to_string() });
+            }
+
+            // Filas: aridad, estados declarados y valores
+            let mut rows_seen: Vec<&Vec<String>> = Vec::new();
+            for row in &node.cpt {
+                if row.given.len() != node.parents.len() {
+                    push(Severity::Error, &node.name, Issue::RowArity { given: row.given.clone(), expected: node.parents.len() });
+                    continue;
+                }
+                if rows_seen.contains(&&row.given) {
+                    push(Severity::Error, &node.name, Issue::DuplicateRow { given: row.given.clone() });
+                }
+                rows_seen.push(&row.given);
+
+                for (parent, state) in node.parents.iter().zip(&row.given) {
+                    if let Some(&p) = by_name.get(parent.as_str())
+                        && !self.nodes[p].states.contains(state)
+                    {
+                        push(Severity::Error, &node.name, Issue::UndeclaredState {
+                            given: row.given.clone(),
+                            state: state.clone(),
+                            declared_in: parent.clone(),
+                        });
+                    }
+                }
+                for (state, value) in &row.probabilities {
+                    if !node.states.contains(state) {
+                        push(Severity::Error, &node.name, Issue::UndeclaredState {
+                            given: row.given.clone(),
+                            state: state.clone(),
+                            declared_in: node.name.clone(),
+                        });
+                    }
+                    if !value.is_finite() || *value < 0.0 || *value > 1.0 {
+                        push(Severity::Error, &node.name, Issue::InvalidProbability {
+                            given: row.given.clone(),
+                            state: state.clone(),
+                            value: *value,
+                        });
+                    }
+                }
+
+                let sum: f64 = row.probabilities.values().sum();
+                if (sum - 1.0).abs() > ROW_SUM_TOLERANCE {
+                    push(Severity::Error, &node.name, Issue::RowSum { given: row.given.clone(), sum });
+                }
+            }
+
+            // Combinaciones de padres sin fila (solo si todos los padres existen)
+            if unknown.is_empty()
+                && let Ok(configurations) = parent_configurations(self, node)
+            {
+                for given in configurations {
+                    if !node.cpt.iter().any(|row| row.given == given) {
+                        push(Severity::Error, &node.name, Issue::MissingRow { given });
+                    }
+                }
+            }
+
+            let has_children = self.nodes.iter().any(|n| n.parents.contains(&node.name));
+            if self.nodes.len() > 1 && node.parents.is_empty() && !has_children {
+                push(Severity::Warning, &node.name, Issue::Orphan);
+            }
+        }
+
+        for cycle in find_cycles(self, &by_name) {
+            push(Severity::Error, &cycle[0].clone(), Issue::Cycle { nodes: cycle });
+        }
+
+        let valid = diagnostics.iter().all(|d| d.severity != Severity::Error);
+        ValidationReport { valid, diagnostics }
+    }
+}
+
+// DFS con tres colores; cada arista de retroceso cierra un ciclo. Los ciclos con el
+// mismo conjunto de nodos se informan una sola vez.
+fn find_cycles(spec: &ModelSpec, by_name: &HashMap<&str, usize>) -> Vec<Vec<String>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Color {
+        White,
+        Grey,
+        Black,
+    }
+
+    // Aristas padre → hijo
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); spec.nodes.len()];
+    for (child, node) in spec.nodes.iter().enumerate() {
+        for parent in &node.parents {
+            if let Some(&p) = by_name.get(parent.as_str()) {
+                children[p].push(child);
+            }
+        }
+    }
+
+    let mut color = vec![Color::White; spec.nodes.len()];
+    let mut cycles: Vec<Vec<String>> = Vec::new();
+    let mut reported: Vec<BTreeSet<usize>> = Vec::new();
+
+    for root in 0..spec.nodes.len() {
+        if color[root] != Color::White {
+            continue;
+        }
+        // Pila de (nodo, siguiente hijo a visitar); `path` es la rama actual
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        let mut path: Vec<usize> = vec![root];
+        color[root] = Color::Grey;
+        while let Some((node, next)) = stack.last_mut() {
+            let node = *node;
+            if let Some(&child) = children[node].get(*next) {
+                *next += 1;
+                match color[child] {
+                    Color::White => {
+                        color[child] = Color::Grey;
+                        stack.push((child, 0));
+                        path.push(child);
+                    }
+                    Color::Grey => {
+                        let start = path.iter().position(|n| *n == child).expect("Grey nodes are on the path");
+                        let members: BTreeSet<usize> = path[start..].iter().copied().collect();
+                        if !reported.contains(&members) {
+                            let mut cycle: Vec<String> = path[start..].iter().map(|n| spec.nodes[*n].name.clone()).collect();
+                            cycle.push(spec.nodes[child].name.clone());
+                            cycles.push(cycle);
+                            reported.push(members);
+                        }
+                    }
+                    Color::Black => {}
+                }
+            } else {
+                color[node] = Color::Black;
+                stack.pop();
+                path.pop();
+            }
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::build::default_model;
+
+    fn kinds(report: &ValidationReport) -> Vec<(&str, &Issue)> {
+        report.diagnostics.iter().map(|d| (d.node.as_str(), &d.issue)).collect()
+    }
+
+    #[test]
+    fn test_default_model_is_valid() {
+        let report = default_model().unwrap().validate();
+        assert!(report.valid, "{:?}", report.diagnostics);
+        assert!(report.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_reports_numeric_problems() {
+        let mut spec = default_model().unwrap();
+        let gas = spec.nodes.iter_mut().find(|n| n.name == "ProduccionGasReal").unwrap();
+        // Fila olvidada, fila que no suma 1 y estado mal escrito
+        gas.cpt.retain(|row| row.given != ["Degradado", "Alto"]);
+        *gas.cpt[0].probabilities.get_mut("Normal").unwrap() = 0.8;
+        let p = gas.cpt[1].probabilities.remove("Alta").unwrap();
+        gas.cpt[1].probabilities.insert("alta".to_string(), p);
+
+        let report = spec.validate();
+        assert!(!report.valid);
+        let found = kinds(&report);
+        assert!(found.contains(&("ProduccionGasReal", &Issue::MissingRow { given: vec!["Degradado".into(), "Alto".into()] })));
+        assert!(found.iter().any(|(_, issue)| matches!(issue, Issue::RowSum { sum, .. } if (sum - 0.95).abs() < 1e-12)));
+        assert!(found.iter().any(|(_, issue)| matches!(issue, Issue::UndeclaredState { state, .. } if state == "alta")));
+        assert!(report.clone().into_result().unwrap_err().to_string().contains("no row for parent states"));
+    }
+
+    #[test]
+    fn test_reports_cycles_and_orphans() {
+        let mut spec = default_model().unwrap();
+        // EstadoMicrobiano → TemperaturaReal → T_sensor → EstadoMicrobiano
+        let micro = spec.nodes.iter_mut().find(|n| n.name == "EstadoMicrobiano").unwrap();
+        micro.parents = vec!["T_sensor".to_string()];
+        micro.cpt = ["baja", "normal", "alta"].iter()
+            .map(|s| crate::model::CptRow {
+                given: vec![s.to_string()],
+                probabilities: [("Bueno".to_string(), 0.5), ("Degradado".to_string(), 0.5)].into(),
+            })
+            .collect();
+        spec.nodes.push(crate::model::NodeSpec {
+            name: "Suelto".to_string(),
+            states: vec!["a".to_string()],
+            parents: Vec::new(),
+            cpt: vec![crate::model::CptRow { given: Vec::new(), probabilities: [("a".to_string(), 1.0)].into() }],
+            metadata: Default::default(),
+        });
+
+        let report = spec.validate();
+        let cycles: Vec<&Vec<String>> = report.diagnostics.iter()
+            .filter_map(|d| match &d.issue { Issue::Cycle { nodes } => Some(nodes), _ => None })
+            .collect();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].first(), cycles[0].last());
+        assert_eq!(cycles[0].len(), 4);
+
+        let orphan = report.diagnostics.iter().find(|d| d.issue == Issue::Orphan).unwrap();
+        assert_eq!((orphan.node.as_str(), orphan.severity), ("Suelto", Severity::Warning));
+        assert!(!report.valid);
+    }
+}